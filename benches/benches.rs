@@ -11,9 +11,9 @@ fn parse_line(c: &mut Criterion) {
 
     c.bench_function("parse_line_root", move |b| {
         b.iter(|| {
-            assert_ne!(
+            assert_eq!(
                 cmdr.parse_line("one-class nested some-action", false, &mut std::io::sink()),
-                LineResult::Unrecognized
+                LineResult::Action(())
             )
         })
     });
@@ -26,13 +26,13 @@ fn parse_line(c: &mut Criterion) {
                 cmdr.parse_line("c", false, &mut std::io::sink()),
                 LineResult::Cancel
             );
-            assert_ne!(
+            assert_eq!(
                 cmdr.parse_line(
                     "more_stuff insider one two three four five six seven eight nine ten",
                     false,
                     &mut std::io::sink()
                 ),
-                LineResult::Unrecognized
+                LineResult::Class
             )
         })
     });
@@ -76,8 +76,8 @@ fn completion_cycle(c: &mut Criterion) {
     });
 }
 
-fn build_cmdr<'a>() -> Commander<'a, ()> {
-    let cmdr = Builder::default_config("root")
+fn build_cmdr() -> Commander<()> {
+    Builder::default_config("root")
         .begin_class("one-class", "")
         .begin_class("nested", "")
         .add_action("some-action", "", |_, _| ())
@@ -98,9 +98,7 @@ fn build_cmdr<'a>() -> Commander<'a, ()> {
         .begin_class("nine", "")
         .begin_class("ten", "")
         .into_commander()
-        .unwrap();
-
-    cmdr
+        .unwrap()
 }
 
 criterion_group!(benches, parse_line, build_completion, completion_cycle);
@@ -0,0 +1,325 @@
+//! `#[derive(CommandTree)]` for [cmdtree](https://docs.rs/cmdtree).
+//!
+//! Use through the `derive` feature of `cmdtree`, see the `cmdtree::command` module for the mapping of types to commands.
+
+extern crate proc_macro;
+
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{
+    parse_macro_input, Attribute, Data, DeriveInput, Error, Expr, Fields, Ident, Lit, LitStr, Meta,
+    Result,
+};
+
+/// Implements `cmdtree::command::CommandTree`.
+///
+/// Enums are classes and their variants commands, structs with named fields are actions.
+/// Doc comments are the help messages, `#[cmdtree(name = "...")]` renames a command or field, and
+/// `#[cmdtree(alias = "...")]` adds an alias. Fields are named by the kebab-case of their identifiers.
+#[proc_macro_derive(CommandTree, attributes(cmdtree))]
+pub fn derive_command_tree(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(&input)
+        .unwrap_or_else(Error::into_compile_error)
+        .into()
+}
+
+fn expand(input: &DeriveInput) -> Result<TokenStream2> {
+    if !input.generics.params.is_empty() {
+        return Err(Error::new_spanned(
+            &input.generics,
+            "CommandTree cannot be derived for generic types",
+        ));
+    }
+
+    match &input.data {
+        Data::Enum(data) => expand_enum(input, data),
+        Data::Struct(data) => expand_struct(input, &data.fields),
+        Data::Union(_) => Err(Error::new_spanned(
+            input,
+            "CommandTree cannot be derived for unions",
+        )),
+    }
+}
+
+fn expand_enum(input: &DeriveInput, data: &syn::DataEnum) -> Result<TokenStream2> {
+    let ident = &input.ident;
+    let mut commands = Vec::new();
+
+    for variant in data.variants.iter() {
+        let v = &variant.ident;
+        let attrs = CommandAttrs::parse(&variant.attrs, v)?;
+        let name = &attrs.name;
+        let help = &attrs.help;
+        let aliases = &attrs.aliases;
+
+        let add = match &variant.fields {
+            Fields::Unit => action(name, help, &Fields::Unit, quote!(#ident::#v))?,
+            Fields::Named(_) => action(name, help, &variant.fields, quote!(#ident::#v))?,
+            Fields::Unnamed(fields) if fields.unnamed.len() == 1 => {
+                let ty = &fields.unnamed[0].ty;
+                quote! {
+                    <#ty as ::cmdtree::command::CommandTree>::add_child(
+                        builder,
+                        #name,
+                        #help,
+                        move |c, w| dispatch(#ident::#v(c), w),
+                    )?
+                }
+            }
+            Fields::Unnamed(_) => {
+                return Err(Error::new_spanned(
+                    &variant.fields,
+                    "a tuple variant must have a single field implementing CommandTree",
+                ))
+            }
+        };
+
+        commands.push(quote! {
+            let builder = {
+                let dispatch = dispatch.clone();
+                #add
+            };
+            #( let builder = builder.add_alias(#name, #aliases)?; )*
+        });
+    }
+
+    Ok(quote! {
+        impl ::cmdtree::command::CommandTree for #ident {
+            fn add_commands<R, S, F>(
+                builder: ::cmdtree::Builder<R, S>,
+                dispatch: F,
+            ) -> ::cmdtree::builder::BuilderResult<R, S>
+            where
+                F: Fn(Self, &mut dyn ::std::io::Write) -> R + Clone + Send + 'static,
+            {
+                #[allow(unused_imports)]
+                use ::cmdtree::BuilderChain;
+                #(#commands)*
+                let _ = dispatch;
+                Ok(builder)
+            }
+
+            fn add_child<R, S, F>(
+                builder: ::cmdtree::Builder<R, S>,
+                name: &str,
+                help: &str,
+                dispatch: F,
+            ) -> ::cmdtree::builder::BuilderResult<R, S>
+            where
+                F: Fn(Self, &mut dyn ::std::io::Write) -> R + Clone + Send + 'static,
+            {
+                use ::cmdtree::BuilderChain;
+                Self::add_commands(builder.begin_class(name, help.to_string())?, dispatch)?.end_class()
+            }
+        }
+    })
+}
+
+fn expand_struct(input: &DeriveInput, fields: &Fields) -> Result<TokenStream2> {
+    if let Fields::Unnamed(_) = fields {
+        return Err(Error::new_spanned(
+            fields,
+            "CommandTree can only be derived for structs with named fields",
+        ));
+    }
+
+    let ident = &input.ident;
+    let attrs = CommandAttrs::parse(&input.attrs, ident)?;
+    let name = &attrs.name;
+    let help = &attrs.help;
+    let aliases = &attrs.aliases;
+    let add = action(
+        &quote!(name),
+        &quote!(help.to_string()),
+        fields,
+        quote!(#ident),
+    )?;
+
+    Ok(quote! {
+        impl ::cmdtree::command::CommandTree for #ident {
+            fn add_commands<R, S, F>(
+                builder: ::cmdtree::Builder<R, S>,
+                dispatch: F,
+            ) -> ::cmdtree::builder::BuilderResult<R, S>
+            where
+                F: Fn(Self, &mut dyn ::std::io::Write) -> R + Clone + Send + 'static,
+            {
+                #[allow(unused_imports)]
+                use ::cmdtree::BuilderChain;
+                let builder = Self::add_child(builder, #name, #help, dispatch)?;
+                #( let builder = builder.add_alias(#name, #aliases)?; )*
+                Ok(builder)
+            }
+
+            fn add_child<R, S, F>(
+                builder: ::cmdtree::Builder<R, S>,
+                name: &str,
+                help: &str,
+                dispatch: F,
+            ) -> ::cmdtree::builder::BuilderResult<R, S>
+            where
+                F: Fn(Self, &mut dyn ::std::io::Write) -> R + Clone + Send + 'static,
+            {
+                use ::cmdtree::BuilderChain;
+                Ok(#add)
+            }
+        }
+    })
+}
+
+/// An expression adding an action to `builder`, which constructs the command from its arguments.
+fn action<N: quote::ToTokens, H: quote::ToTokens>(
+    name: &N,
+    help: &H,
+    fields: &Fields,
+    construct: TokenStream2,
+) -> Result<TokenStream2> {
+    let (spec, command) = match fields {
+        Fields::Named(fields) => {
+            let idents: Vec<_> = fields.named.iter().map(|f| &f.ident).collect();
+            let types = fields.named.iter().map(|f| &f.ty);
+            let names = fields
+                .named
+                .iter()
+                .map(field_name)
+                .collect::<Result<Vec<_>>>()?;
+            (
+                quote! {
+                    let spec = ::cmdtree::args::ArgSpec::new();
+                    #( let spec = <#types as ::cmdtree::command::ArgField>::add_arg(spec, #names); )*
+                },
+                quote! {
+                    #construct {
+                        #( #idents: ::cmdtree::command::ArgField::from_args(args, #names), )*
+                    }
+                },
+            )
+        }
+        _ => (
+            quote!(let spec = ::cmdtree::args::ArgSpec::new();),
+            construct,
+        ),
+    };
+
+    Ok(quote! {{
+        #spec
+        builder.add_action_with_args(#name, #help, spec, move |w, args| {
+            let _ = args;
+            dispatch(#command, w)
+        })?
+    }})
+}
+
+/// The argument name of a field, its `#[cmdtree(name = "...")]` or the kebab-case of its identifier.
+fn field_name(field: &syn::Field) -> Result<String> {
+    let mut name = None;
+
+    for attr in field.attrs.iter().filter(|a| a.path().is_ident("cmdtree")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("name") {
+                name = Some(meta.value()?.parse::<LitStr>()?.value());
+                Ok(())
+            } else {
+                Err(meta.error("expected `name`"))
+            }
+        })?;
+    }
+
+    Ok(name.unwrap_or_else(|| {
+        field
+            .ident
+            .as_ref()
+            .map(|i| arg_name(i).replace('_', "-"))
+            .unwrap_or_default()
+    }))
+}
+
+/// The name, help and aliases of a command.
+struct CommandAttrs {
+    name: String,
+    help: String,
+    aliases: Vec<String>,
+}
+
+impl CommandAttrs {
+    fn parse(attrs: &[Attribute], ident: &Ident) -> Result<Self> {
+        let mut name = None;
+        let mut aliases = Vec::new();
+        let mut docs = Vec::new();
+
+        for attr in attrs {
+            if attr.path().is_ident("doc") {
+                if let Meta::NameValue(nv) = &attr.meta {
+                    if let Expr::Lit(lit) = &nv.value {
+                        if let Lit::Str(s) = &lit.lit {
+                            docs.push(s.value().trim().to_string());
+                        }
+                    }
+                }
+            } else if attr.path().is_ident("cmdtree") {
+                attr.parse_nested_meta(|meta| {
+                    if meta.path.is_ident("name") {
+                        name = Some(meta.value()?.parse::<LitStr>()?.value());
+                        Ok(())
+                    } else if meta.path.is_ident("alias") {
+                        aliases.push(meta.value()?.parse::<LitStr>()?.value());
+                        Ok(())
+                    } else {
+                        Err(meta.error("expected `name` or `alias`"))
+                    }
+                })?;
+            }
+        }
+
+        Ok(CommandAttrs {
+            name: name.unwrap_or_else(|| kebab_case(&arg_name(ident))),
+            help: docs
+                .into_iter()
+                .filter(|x| !x.is_empty())
+                .collect::<Vec<_>>()
+                .join(" "),
+            aliases,
+        })
+    }
+}
+
+/// The identifier as written, without a raw identifier prefix.
+fn arg_name(ident: &Ident) -> String {
+    let s = ident.to_string();
+    s.strip_prefix("r#").map(String::from).unwrap_or(s)
+}
+
+/// Converts `CamelCase` to `kebab-case`, keeping acronyms together, such as `HTTPServer` to `http-server`.
+fn kebab_case(s: &str) -> String {
+    let chars: Vec<char> = s.chars().collect();
+    let mut kebab = String::new();
+
+    for (i, &c) in chars.iter().enumerate() {
+        if c.is_uppercase() && i > 0 {
+            let prev = chars[i - 1];
+            let next_lower = chars.get(i + 1).is_some_and(|x| x.is_lowercase());
+            if prev.is_lowercase() || prev.is_numeric() || (prev.is_uppercase() && next_lower) {
+                kebab.push('-');
+            }
+        }
+        kebab.extend(c.to_lowercase());
+    }
+
+    kebab
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn kebab_case_test() {
+        assert_eq!(kebab_case("Echo"), "echo");
+        assert_eq!(kebab_case("CountDown"), "count-down");
+        assert_eq!(kebab_case("HTTPServer"), "http-server");
+        assert_eq!(kebab_case("Step2Go"), "step2-go");
+        assert_eq!(kebab_case("IO"), "io");
+    }
+}
@@ -0,0 +1,196 @@
+use cmdtree::command::{CommandTree, Handler};
+use cmdtree::*;
+use std::io::{self, Write};
+
+#[derive(CommandTree, Debug, PartialEq)]
+enum Cmd {
+    /// Prints the words.
+    ///
+    /// Separated by spaces.
+    Echo {
+        words: Vec<String>,
+    },
+    /// Counts down.
+    #[cmdtree(alias = "cd")]
+    CountDown {
+        n: i64,
+        step: Option<f64>,
+        r#loud: bool,
+    },
+    /// Server commands.
+    #[cmdtree(name = "srv", alias = "s")]
+    Server(ServerCmd),
+    /// Connects somewhere.
+    Connect(Connect),
+    Quit,
+}
+
+#[derive(CommandTree, Debug, PartialEq)]
+enum ServerCmd {
+    /// Starts the server.
+    HTTPStart,
+}
+
+/// Connect to a host.
+#[derive(CommandTree, Debug, PartialEq)]
+#[cmdtree(alias = "conn")]
+struct Connect {
+    host: String,
+}
+
+struct Recorder(Vec<Cmd>);
+
+impl Handler<Cmd> for Recorder {
+    type Output = usize;
+
+    fn handle(&mut self, command: Cmd, writer: &mut dyn Write) -> usize {
+        writeln!(writer, "{:?}", command).unwrap();
+        self.0.push(command);
+        self.0.len()
+    }
+}
+
+#[test]
+fn derive_enum_test() {
+    let mut cmder = Cmd::builder("base", Recorder(Vec::new()))
+        .into_commander()
+        .unwrap();
+    let w = &mut io::sink();
+
+    assert_eq!(
+        cmder.parse_line("echo a b", false, w),
+        LineResult::Action(1)
+    );
+    assert_eq!(
+        cmder.parse_line("cd 3 --loud", false, w),
+        LineResult::Action(2)
+    );
+    assert_eq!(
+        cmder.parse_line("s http-start", false, w),
+        LineResult::Action(3)
+    );
+    assert_eq!(
+        cmder.parse_line("connect local", false, w),
+        LineResult::Action(4)
+    );
+    assert_eq!(cmder.parse_line("quit", false, w), LineResult::Action(5));
+    assert!(matches!(
+        cmder.parse_line("count-down three", false, w),
+        LineResult::InvalidArgs(_)
+    ));
+    assert!(matches!(
+        cmder.parse_line("quit now", false, w),
+        LineResult::InvalidArgs(_)
+    ));
+
+    let mut out = Vec::new();
+    cmder.parse_line("count-down 1 0.5", false, &mut out);
+    assert_eq!(
+        String::from_utf8(out).unwrap(),
+        "CountDown { n: 1, step: Some(0.5), loud: false }\n"
+    );
+
+    let tree = cmder.tree(true);
+    let names: Vec<_> = tree.actions.iter().map(|a| a.name.as_str()).collect();
+    assert_eq!(names, vec!["echo", "count-down", "connect", "quit"]);
+    assert_eq!(
+        tree.actions[0].help,
+        "Prints the words. Separated by spaces."
+    );
+    assert_eq!(tree.actions[1].aliases, vec!["cd".to_string()]);
+    assert_eq!(tree.actions[1].usage, "[--loud] <n> [step]");
+    assert_eq!(tree.actions[3].help, "");
+    assert_eq!(tree.classes[0].name, "srv");
+    assert_eq!(tree.classes[0].help, "Server commands.");
+    assert_eq!(tree.classes[0].actions[0].name, "http-start");
+}
+
+#[test]
+fn derive_struct_test() {
+    let mut cmder = Builder::new("base")
+        .begin_class("one", "")
+        .and_then(|b| Connect::add_commands(b, |c, _| c.host))
+        .into_commander()
+        .unwrap();
+
+    assert_eq!(
+        cmder.parse_line("one conn local", false, &mut io::sink()),
+        LineResult::Action("local".to_string())
+    );
+
+    let tree = cmder.tree(true);
+    assert_eq!(tree.classes[0].actions[0].name, "connect");
+    assert_eq!(tree.classes[0].actions[0].help, "Connect to a host.");
+}
+
+#[derive(CommandTree, Debug, PartialEq)]
+struct Resize {
+    width: u16,
+    height: Option<i32>,
+    scales: Vec<f32>,
+}
+
+#[test]
+fn derive_int_fields_test() {
+    let mut cmder = Resize::add_commands(Builder::new("base"), |r, _| r)
+        .into_commander()
+        .unwrap();
+    let w = &mut io::sink();
+
+    assert_eq!(
+        cmder.parse_line("resize 65535 -2 0.5", false, w),
+        LineResult::Action(Resize {
+            width: 65535,
+            height: Some(-2),
+            scales: vec![0.5],
+        })
+    );
+    assert_eq!(
+        cmder.parse_line("resize 65536", false, w),
+        LineResult::InvalidArgs(args::ArgError::Invalid {
+            name: "width".to_string(),
+            value: "65536".to_string(),
+            expected: args::ArgType::IntRange(0, 65535),
+        })
+    );
+    assert_eq!(
+        cmder.parse_line("resize 1 2 0.5 1e300", false, w),
+        LineResult::InvalidArgs(args::ArgError::Invalid {
+            name: "scales".to_string(),
+            value: "1e300".to_string(),
+            expected: args::ArgType::Float32,
+        })
+    );
+}
+
+#[derive(CommandTree, Debug, PartialEq)]
+struct Deploy {
+    target_host: String,
+    dry_run: bool,
+    #[cmdtree(name = "n")]
+    max_retries: Option<u8>,
+}
+
+#[test]
+fn derive_field_names_test() {
+    let mut cmder = Deploy::add_commands(Builder::new("base"), |d, _| d)
+        .into_commander()
+        .unwrap();
+    let w = &mut io::sink();
+
+    assert_eq!(
+        cmder.parse_line("deploy local --dry-run 3", false, w),
+        LineResult::Action(Deploy {
+            target_host: "local".to_string(),
+            dry_run: true,
+            max_retries: Some(3),
+        })
+    );
+    assert!(matches!(
+        cmder.parse_line("deploy local --dry_run", false, w),
+        LineResult::InvalidArgs(_)
+    ));
+
+    let tree = cmder.tree(true);
+    assert_eq!(tree.actions[0].usage, "[--dry-run] <target-host> [n]");
+}
@@ -1,4 +1,4 @@
-//! Example on implementing an argument completer.
+//! Example on adding an argument completer to an action.
 //!
 //! try running and hitting TAB with the following inputs to see completion!
 //! ```
@@ -7,55 +7,32 @@
 //! arg-completion.nested => path
 //! arg-completion=> no-complete  <-- won't see any completions!
 
-use cmdtree::completion::*;
+use cmdtree::completion::TreeCompleter;
 use cmdtree::{Builder, BuilderChain};
 
 fn main() {
     let cmder = Builder::default_config("arg-completion")
         .add_action("path", "complete path names", |_, _| ())
+        .add_arg_completer("path", complete_path)
         .add_action("no-complete", "", |_, _| ())
         .begin_class("nested", "")
         .add_action("path", "", |_, _| ())
+        .add_arg_completer("path", complete_path)
         .into_commander()
         .unwrap();
 
-    cmder.run_with_completion(|c| ArgCompleter {
-        items: create_action_completion_items(c),
-    });
+    cmder.run_with_completion(TreeCompleter::new);
 }
 
-struct ArgCompleter {
-    items: Vec<ActionMatch>,
-}
-
-impl<T: Terminal> Completer<T> for ArgCompleter {
-    fn complete(
-        &self,
-        _word: &str,
-        prompter: &Prompter<T>,
-        _start: usize,
-        end: usize,
-    ) -> Option<Vec<Completion>> {
-        // only want the completion on the 'path' action
-        // notice the syntax for qualified paths.
-        let actions = [".path", "nested..path"];
-
-        let line = prompter.buffer();
-
-        // retrieve the matched action is there is one.
-        // also check that the action is one that should be completed on, using qualified path
-        let action_match = self.items.iter().find(|x| {
-            line.starts_with(x.info.completestr.as_str())
-                && actions.contains(&x.qualified_path.as_str())
-        })?;
-
-        // going to use this logic for compeletions, but can implement own
-        let path_completer = linefeed::complete::PathCompleter;
-
-        let arg_line = &line[action_match.info.completestr.len()..]; // portion of line just for args
-        let word_start = linefeed::complete::word_break_start(arg_line, " "); // break on spaces
-        let word = &arg_line[word_start..];
-
-        path_completer.complete(word, prompter, word_start, end)
-    }
+/// Completes the entries of the current directory which start with `word`.
+fn complete_path(_state: &(), _args: &[&str], word: &str) -> Vec<String> {
+    std::fs::read_dir(".")
+        .map(|entries| {
+            entries
+                .filter_map(|e| e.ok())
+                .map(|e| e.file_name().to_string_lossy().into_owned())
+                .filter(|name| name.starts_with(word))
+                .collect()
+        })
+        .unwrap_or_default()
 }
@@ -1,6 +1,8 @@
-//! Example on implementing a completer.
+//! Example on using the built-in tree completer.
+//!
+//! See `arg-completion.rs` for completing the arguments of an action.
 
-use cmdtree::completion::*;
+use cmdtree::completion::TreeCompleter;
 use cmdtree::{Builder, BuilderChain};
 
 fn main() {
@@ -16,27 +18,5 @@ fn main() {
         .into_commander()
         .unwrap();
 
-    cmder.run_with_completion(|c| TreeCompleter {
-        items: create_tree_completion_items(c),
-    });
-}
-
-struct TreeCompleter {
-    items: Vec<CompletionInfo>,
-}
-
-impl<T: Terminal> Completer<T> for TreeCompleter {
-    fn complete(
-        &self,
-        _word: &str,
-        prompter: &Prompter<T>,
-        _start: usize,
-        _end: usize,
-    ) -> Option<Vec<Completion>> {
-        Some(
-            tree_completions(prompter.buffer(), self.items.iter())
-                .map(|x| Completion::simple(x.0.to_string()))
-                .collect(),
-        )
-    }
+    cmder.run_with_completion(TreeCompleter::new);
 }
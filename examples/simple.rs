@@ -1,6 +1,7 @@
 //! Simple example highlighting nesting of commands
 
 extern crate cmdtree;
+use cmdtree::args::{ArgSpec, ArgType};
 use cmdtree::*;
 
 fn main() {
@@ -16,20 +17,16 @@ fn main() {
         .add_action("echo", "repeat stuff", |wtr, args| {
             writeln!(wtr, "{}", args.join(" ")).unwrap()
         })
-        .add_action("countdown", "countdown from a number", |wtr, args| {
-            if args.len() != 1 {
-                println!("need one number",);
-            } else {
-                match str::parse::<u32>(args[0]) {
-                    Ok(n) => {
-                        for i in (0..=n).rev() {
-                            writeln!(wtr, "{}", i).unwrap();
-                        }
-                    }
-                    Err(_) => writeln!(wtr, "expecting a number!",).unwrap(),
+        .add_action_with_args(
+            "countdown",
+            "countdown from a number",
+            ArgSpec::new().required("n", ArgType::Int),
+            |wtr, args| {
+                for i in (0..=args.get_int("n").unwrap()).rev() {
+                    writeln!(wtr, "{}", i).unwrap();
                 }
-            }
-        })
+            },
+        )
         .into_commander() // can short-circuit the closing out of classes
         .unwrap();
 
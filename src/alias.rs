@@ -0,0 +1,232 @@
+//! Runtime aliases, defined with the `alias` builtin or programmatically.
+use super::*;
+
+impl<R, S> Commander<R, S> {
+    /// Set whether the `alias` and `unalias` builtins are available, with their default names.
+    ///
+    /// Off by default, they can also be configured with [`with_builtins`](builder::BuilderChain::with_builtins). When set, the following commands can be entered as the first word of a line:
+    ///
+    /// - `alias` lists the defined aliases.
+    /// - `alias <name>` shows the expansion of an alias.
+    /// - `alias <name> = <command...>` defines an alias.
+    /// - `unalias <name>` removes an alias.
+    ///
+    /// Aliases are expanded regardless of this setting, see [`set_runtime_alias`](Commander::set_runtime_alias).
+    ///
+    /// As with `with_builtins`, enabling the builtins fails if a class or action anywhere in the tree has
+    /// one of their names, returning `BuildError::NameExistsAsClass` or `BuildError::NameExistsAsAction`,
+    /// or if another builtin has one of their names, returning `BuildError::DuplicateBuiltin`.
+    ///
+    /// # Example
+    /// ```rust
+    /// # use cmdtree::*;
+    /// let mut cmder = Builder::new("base")
+    ///     .begin_class("env", "")
+    ///     .add_action("release", "", |_, args| args == ["prod", "push"])
+    ///     .into_commander().unwrap();
+    ///
+    /// cmder.set_alias_builtin(true).unwrap();
+    /// cmder.parse_line("alias deploy = env release prod", false, &mut std::io::sink());
+    /// assert_eq!(cmder.parse_line("deploy push", false, &mut std::io::sink()), LineResult::Action(true));
+    /// ```
+    pub fn set_alias_builtin(&mut self, enable: bool) -> Result<(), BuildError> {
+        let builtins = self.builtins.clone();
+        self.set_builtins(if enable {
+            builtins.enable(Builtin::Alias).enable(Builtin::Unalias)
+        } else {
+            builtins.disable(Builtin::Alias).disable(Builtin::Unalias)
+        })
+    }
+
+    /// The runtime aliases, mapping the alias name to its expansion.
+    pub fn runtime_aliases(&self) -> &BTreeMap<String, String> {
+        &self.runtime_aliases
+    }
+
+    /// Define a runtime alias, returning the previous expansion if the alias existed.
+    ///
+    /// When the first word of a line matches the name, it is replaced with the words of the expansion
+    /// before the line is resolved against the tree, so it can expand to a builtin command.
+    /// Expansions are not expanded again.
+    /// The expansion follows the quoting rules of [`parse_line`](Commander::parse_line).
+    ///
+    /// The name cannot shadow an enabled builtin command or a class or action anywhere in the tree.
+    /// A builtin name returns `BuildError::NameIsBuiltin`, an action name `BuildError::NameExistsAsAction`,
+    /// and a class name `BuildError::NameExistsAsClass`.
+    /// A builtin enabled after the alias is defined takes precedence over it.
+    ///
+    /// # Example
+    /// ```rust
+    /// # use cmdtree::*;
+    /// let mut cmder = Builder::default_config("base")
+    ///     .begin_class("net", "")
+    ///     .add_action("status", "", |_, _| ())
+    ///     .into_commander().unwrap();
+    ///
+    /// assert_eq!(cmder.set_runtime_alias("st", "net status"), Ok(None));
+    /// assert_eq!(cmder.set_runtime_alias("exit", "net status"), Err(BuildError::NameIsBuiltin("exit".to_string())));
+    /// assert_eq!(cmder.set_runtime_alias("status", "net status"), Err(BuildError::NameExistsAsAction));
+    /// assert_eq!(cmder.set_runtime_alias("NET", "net status"), Err(BuildError::NameExistsAsClass));
+    /// ```
+    pub fn set_runtime_alias(
+        &mut self,
+        name: &str,
+        expansion: &str,
+    ) -> Result<Option<String>, BuildError> {
+        let name = name.to_lowercase();
+        if self.builtins.find(&name).is_some() {
+            return Err(BuildError::NameIsBuiltin(name));
+        }
+        self.root.check_tree(|n| n == name)?;
+
+        Ok(self
+            .runtime_aliases
+            .insert(name, expansion.trim().to_string()))
+    }
+
+    /// Remove a runtime alias, returning its expansion if it existed.
+    pub fn remove_runtime_alias(&mut self, name: &str) -> Option<String> {
+        self.runtime_aliases.remove(&name.to_lowercase())
+    }
+
+    /// Write the runtime aliases, one `name = expansion` per line.
+    /// The output can be read back with [`load_runtime_aliases`](Commander::load_runtime_aliases).
+    pub fn save_runtime_aliases<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        for (name, expansion) in self.runtime_aliases.iter() {
+            writeln!(writer, "{} = {}", name, expansion)?;
+        }
+        Ok(())
+    }
+
+    /// Read runtime aliases of the form `name = expansion`, one per line, adding them to the defined aliases.
+    ///
+    /// Blank lines and lines starting with `#` are skipped.
+    /// A line without an `=`, with an empty or multiple word name, or with a name
+    /// rejected by [`set_runtime_alias`](Commander::set_runtime_alias), is an `InvalidData` error.
+    ///
+    /// # Example
+    /// ```rust
+    /// # use cmdtree::*;
+    /// let mut cmder = Builder::default_config("base").into_commander().unwrap();
+    ///
+    /// cmder.load_runtime_aliases("# saved aliases\nls = list all\n".as_bytes()).unwrap();
+    /// assert_eq!(cmder.runtime_aliases().get("ls").map(String::as_str), Some("list all"));
+    ///
+    /// let mut saved = Vec::new();
+    /// cmder.save_runtime_aliases(&mut saved).unwrap();
+    /// assert_eq!(saved, b"ls = list all\n");
+    /// ```
+    pub fn load_runtime_aliases<B: BufRead>(&mut self, reader: B) -> io::Result<()> {
+        for line in reader.lines() {
+            let line = line?;
+            let trimmed = line.trim();
+            if trimmed.is_empty() || trimmed.starts_with('#') {
+                continue;
+            }
+
+            let defined = parse_alias_line(trimmed)
+                .and_then(|(name, expansion)| self.set_runtime_alias(name, expansion).ok());
+            if defined.is_none() {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("invalid alias definition: {}", line),
+                ));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Splits `name = expansion`, returning `None` if the name is empty or contains whitespace.
+fn parse_alias_line(line: &str) -> Option<(&str, &str)> {
+    let idx = line.find('=')?;
+    let name = line[..idx].trim();
+    if name.is_empty() || name.contains(char::is_whitespace) {
+        None
+    } else {
+        Some((name, line[idx + 1..].trim()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_alias_line_test() {
+        assert_eq!(parse_alias_line("a = b c"), Some(("a", "b c")));
+        assert_eq!(parse_alias_line("a=b=c"), Some(("a", "b=c")));
+        assert_eq!(parse_alias_line("a ="), Some(("a", "")));
+        assert_eq!(parse_alias_line(" = b"), None);
+        assert_eq!(parse_alias_line("a b = c"), None);
+        assert_eq!(parse_alias_line("a b"), None);
+    }
+
+    #[test]
+    fn save_load_runtime_aliases_test() {
+        let mut cmder = Builder::default_config("base").into_commander().unwrap();
+        cmder
+            .set_runtime_alias("DEPLOY", " env prod 'release now' ")
+            .unwrap();
+        cmder.set_runtime_alias("st", "net status").unwrap();
+        assert_eq!(cmder.remove_runtime_alias("nope"), None);
+
+        let mut saved = Vec::new();
+        cmder.save_runtime_aliases(&mut saved).unwrap();
+        assert_eq!(
+            String::from_utf8(saved.clone()).unwrap(),
+            "deploy = env prod 'release now'\nst = net status\n"
+        );
+
+        let mut loaded = Builder::default_config("base").into_commander().unwrap();
+        loaded.load_runtime_aliases(saved.as_slice()).unwrap();
+        assert_eq!(loaded.runtime_aliases(), cmder.runtime_aliases());
+
+        let err = loaded
+            .load_runtime_aliases("ok = fine\nnot an alias".as_bytes())
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        let err = loaded
+            .load_runtime_aliases("c = help".as_bytes())
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn set_runtime_alias_names_test() {
+        let mut cmder = Builder::default_config("base")
+            .begin_class("one", "")
+            .begin_class("two", "")
+            .add_action("deep", "", |_, _| ())
+            .into_commander()
+            .unwrap();
+
+        let exists = |cmder: &mut Commander<()>, name| cmder.set_runtime_alias(name, "one");
+        assert_eq!(
+            exists(&mut cmder, "c"),
+            Err(BuildError::NameIsBuiltin("c".to_string()))
+        );
+        assert_eq!(
+            exists(&mut cmder, "Help"),
+            Err(BuildError::NameIsBuiltin("help".to_string()))
+        );
+        assert_eq!(
+            exists(&mut cmder, "deep"),
+            Err(BuildError::NameExistsAsAction)
+        );
+        assert_eq!(
+            exists(&mut cmder, "two"),
+            Err(BuildError::NameExistsAsClass)
+        );
+        assert_eq!(exists(&mut cmder, "alias"), Ok(None));
+        assert_eq!(exists(&mut cmder, "alias"), Ok(Some("one".to_string())));
+
+        // an existing alias is shadowed by a builtin enabled later
+        cmder.set_alias_builtin(true).unwrap();
+        assert_eq!(
+            cmder.parse_line("alias", false, &mut io::sink()),
+            LineResult::Alias
+        );
+    }
+}
@@ -0,0 +1,883 @@
+//! Typed argument specifications for actions.
+//!
+//! An [`ArgSpec`] declares the positional arguments, flags and options an action accepts.
+//! When an action is added with [`add_action_with_args`](crate::BuilderChain::add_action_with_args),
+//! the raw words are validated and converted against the specification _before_ the closure is invoked.
+//! If validation fails, a usage error is written and `parse_line` returns `LineResult::InvalidArgs`.
+//!
+//! # Example
+//!
+//! ```rust
+//! use cmdtree::*;
+//! use cmdtree::args::*;
+//!
+//! let mut cmder = Builder::new("base")
+//!     .add_action_with_args(
+//!         "countdown",
+//!         "countdown from a number",
+//!         ArgSpec::new().required("n", ArgType::Int),
+//!         |_wtr, args| args.get_int("n").unwrap(),
+//!     )
+//!     .into_commander().unwrap();
+//!
+//! let w = &mut std::io::sink();
+//! assert_eq!(cmder.parse_line("countdown 3", false, w), LineResult::Action(3));
+//! assert_eq!(
+//!     cmder.parse_line("countdown three", false, w),
+//!     LineResult::InvalidArgs(ArgError::Invalid {
+//!         name: "n".to_string(),
+//!         value: "three".to_string(),
+//!         expected: ArgType::Int,
+//!     })
+//! );
+//! ```
+//!
+//! # Flags and options
+//!
+//! Flags and options are parsed GNU style before positional arguments are matched.
+//!
+//! - Long flags are given as `--verbose`, short flags as `-v`. Short flags can be bundled, `-vq`.
+//! - Options take a value: `--out file`, `--out=file`, `-o file` or `-ofile`.
+//! - `--` stops flag parsing, all following words are positional.
+//! - Words that look like negative numbers, a `-` followed by a digit or `.`, are positional unless a matching short flag exists.
+//!
+//! Flag and option values are retrieved by their long name. If an option is repeated, the last value is returned by `get`.
+//!
+//! ```rust
+//! use cmdtree::*;
+//! use cmdtree::args::*;
+//!
+//! let spec = ArgSpec::new()
+//!     .flag("verbose", Some('v'))
+//!     .option("out", Some('o'), ArgType::Str)
+//!     .variadic("files", ArgType::Str);
+//!
+//! let args = spec.parse(&["-vo", "out.txt", "a", "--", "--b"]).unwrap();
+//! assert!(args.flag("verbose"));
+//! assert_eq!(args.get_str("out"), Some("out.txt"));
+//! assert_eq!(args.get_all("files").len(), 2);
+//!
+//! assert_eq!(spec.parse(&["-x"]), Err(ArgError::UnknownFlag("-x".to_string())));
+//! ```
+use std::fmt;
+
+/// The type an argument is converted to.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum ArgType {
+    /// Any string.
+    Str,
+    /// A signed integer, stored as an `i64`.
+    Int,
+    /// A signed integer from `min` to `max` inclusive, stored as an `i64`.
+    IntRange(i64, i64),
+    /// A floating point number, stored as an `f64`.
+    Float,
+    /// A floating point number which does not overflow an `f32`, stored as an `f64`.
+    Float32,
+    /// A boolean. Accepts `true`/`false`, `yes`/`no`, `on`/`off` and `1`/`0`.
+    Bool,
+}
+
+impl ArgType {
+    /// Convert `value` into this type.
+    pub fn parse(self, value: &str) -> Option<ArgValue> {
+        match self {
+            ArgType::Str => Some(ArgValue::Str(value.to_string())),
+            ArgType::Int => value.parse().ok().map(ArgValue::Int),
+            ArgType::IntRange(min, max) => value
+                .parse()
+                .ok()
+                .filter(|i| (min..=max).contains(i))
+                .map(ArgValue::Int),
+            ArgType::Float => value.parse().ok().map(ArgValue::Float),
+            ArgType::Float32 => value
+                .parse()
+                .ok()
+                .filter(|f| fits_f32(*f))
+                .map(ArgValue::Float),
+            ArgType::Bool => match value.to_lowercase().as_str() {
+                "true" | "yes" | "on" | "1" => Some(ArgValue::Bool(true)),
+                "false" | "no" | "off" | "0" => Some(ArgValue::Bool(false)),
+                _ => None,
+            },
+        }
+    }
+}
+
+/// Whether the value does not overflow when converted to an `f32`, infinities and NaN are kept.
+pub(crate) fn fits_f32(f: f64) -> bool {
+    !f.is_finite() || (f as f32).is_finite()
+}
+
+impl fmt::Display for ArgType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ArgType::Str => write!(f, "string"),
+            ArgType::Int => write!(f, "integer"),
+            ArgType::IntRange(min, max) => write!(f, "integer from {} to {}", min, max),
+            ArgType::Float => write!(f, "number"),
+            ArgType::Float32 => write!(f, "32-bit number"),
+            ArgType::Bool => write!(f, "boolean"),
+        }
+    }
+}
+
+/// A converted argument value.
+#[derive(Debug, PartialEq, Clone)]
+pub enum ArgValue {
+    /// String value.
+    Str(String),
+    /// Integer value.
+    Int(i64),
+    /// Floating point value.
+    Float(f64),
+    /// Boolean value.
+    Bool(bool),
+}
+
+impl ArgValue {
+    /// The inner string, if a `Str`.
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            ArgValue::Str(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    /// The inner integer, if an `Int`.
+    pub fn as_int(&self) -> Option<i64> {
+        match self {
+            ArgValue::Int(i) => Some(*i),
+            _ => None,
+        }
+    }
+
+    /// The inner number, if a `Float` or an `Int`.
+    pub fn as_float(&self) -> Option<f64> {
+        match self {
+            ArgValue::Float(f) => Some(*f),
+            ArgValue::Int(i) => Some(*i as f64),
+            _ => None,
+        }
+    }
+
+    /// The inner boolean, if a `Bool`.
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            ArgValue::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+}
+
+/// How many values an argument takes.
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum Arity {
+    /// Exactly one value must be supplied.
+    Required,
+    /// Zero or one value, with an optional default used when no value is supplied.
+    Optional(Option<String>),
+    /// Zero or more values. Must be the last argument.
+    Variadic,
+}
+
+/// A single positional argument definition.
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Arg {
+    /// The argument name.
+    pub name: String,
+    /// The type values are converted to.
+    pub argtype: ArgType,
+    /// How many values the argument takes.
+    pub arity: Arity,
+}
+
+/// A flag or option definition.
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Flag {
+    /// The long name, used as `--long`.
+    pub long: String,
+    /// The optional short name, used as `-s`.
+    pub short: Option<char>,
+    /// The type of the value if the flag is an option. `None` for a switch.
+    pub argtype: Option<ArgType>,
+    /// The value an option takes when not supplied.
+    pub default: Option<String>,
+}
+
+impl Flag {
+    fn usage(&self) -> String {
+        let name = match self.short {
+            Some(s) => format!("-{}|--{}", s, self.long),
+            None => format!("--{}", self.long),
+        };
+
+        match (&self.argtype, &self.default) {
+            (None, _) => format!("[{}]", name),
+            (Some(_), None) => format!("[{} <{}>]", name, self.long),
+            (Some(_), Some(d)) => format!("[{} <{}={}>]", name, self.long, d),
+        }
+    }
+}
+
+/// Argument specification for an action.
+///
+/// Positional arguments are matched in the order they are declared.
+/// Required arguments must come before optional arguments, and a variadic argument must come last.
+/// Flags and options can appear anywhere amongst the positional arguments.
+#[derive(Debug, PartialEq, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ArgSpec {
+    args: Vec<Arg>,
+    flags: Vec<Flag>,
+}
+
+impl ArgSpec {
+    /// A specification with no arguments.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add a required argument.
+    pub fn required(self, name: &str, argtype: ArgType) -> Self {
+        self.push(name, argtype, Arity::Required)
+    }
+
+    /// Add an optional argument.
+    pub fn optional(self, name: &str, argtype: ArgType) -> Self {
+        self.push(name, argtype, Arity::Optional(None))
+    }
+
+    /// Add an optional argument which takes `value` when not supplied.
+    pub fn with_default(self, name: &str, argtype: ArgType, value: &str) -> Self {
+        self.push(name, argtype, Arity::Optional(Some(value.to_string())))
+    }
+
+    /// Add an argument which collects all remaining values.
+    pub fn variadic(self, name: &str, argtype: ArgType) -> Self {
+        self.push(name, argtype, Arity::Variadic)
+    }
+
+    /// Add a switch, such as `--verbose` or `-v`.
+    pub fn flag(self, long: &str, short: Option<char>) -> Self {
+        self.push_flag(long, short, None, None)
+    }
+
+    /// Add an option which takes a value, such as `--out file` or `-o file`.
+    pub fn option(self, long: &str, short: Option<char>, argtype: ArgType) -> Self {
+        self.push_flag(long, short, Some(argtype), None)
+    }
+
+    /// Add an option which takes `value` when not supplied.
+    pub fn option_with_default(
+        self,
+        long: &str,
+        short: Option<char>,
+        argtype: ArgType,
+        value: &str,
+    ) -> Self {
+        self.push_flag(long, short, Some(argtype), Some(value.to_string()))
+    }
+
+    fn push(mut self, name: &str, argtype: ArgType, arity: Arity) -> Self {
+        self.args.push(Arg {
+            name: name.to_string(),
+            argtype,
+            arity,
+        });
+        self
+    }
+
+    fn push_flag(
+        mut self,
+        long: &str,
+        short: Option<char>,
+        argtype: Option<ArgType>,
+        default: Option<String>,
+    ) -> Self {
+        self.flags.push(Flag {
+            long: long.to_string(),
+            short,
+            argtype,
+            default,
+        });
+        self
+    }
+
+    /// The declared positional arguments, in order.
+    pub fn args(&self) -> &[Arg] {
+        &self.args
+    }
+
+    /// The declared flags and options, in order.
+    pub fn flags(&self) -> &[Flag] {
+        &self.flags
+    }
+
+    /// Returns `true` if there are no positional arguments, flags or options.
+    pub fn is_empty(&self) -> bool {
+        self.args.is_empty() && self.flags.is_empty()
+    }
+
+    /// A usage string, such as `[-v|--verbose] [-o|--out <out>] <n> [step] [unit=s] [labels...]`.
+    pub fn usage(&self) -> String {
+        self.flags
+            .iter()
+            .map(Flag::usage)
+            .chain(self.args.iter().map(|a| match &a.arity {
+                Arity::Required => format!("<{}>", a.name),
+                Arity::Optional(None) => format!("[{}]", a.name),
+                Arity::Optional(Some(d)) => format!("[{}={}]", a.name, d),
+                Arity::Variadic => format!("[{}...]", a.name),
+            }))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// Checks the specification is well formed.
+    /// Returns a description of the problem if not.
+    pub fn validate(&self) -> Result<(), String> {
+        let mut optional = false;
+        let names = self
+            .args
+            .iter()
+            .map(|a| &a.name)
+            .chain(self.flags.iter().map(|f| &f.long))
+            .collect::<Vec<_>>();
+
+        for (idx, name) in names.iter().enumerate() {
+            if names[..idx].contains(name) {
+                return Err(format!("argument '{}' is declared twice", name));
+            }
+        }
+
+        for (idx, arg) in self.args.iter().enumerate() {
+            match &arg.arity {
+                Arity::Required if optional => {
+                    return Err(format!(
+                        "required argument '{}' follows an optional argument",
+                        arg.name
+                    ))
+                }
+                Arity::Required => (),
+                Arity::Optional(default) => {
+                    optional = true;
+                    check_default(&arg.name, arg.argtype, default)?;
+                }
+                Arity::Variadic if idx + 1 != self.args.len() => {
+                    return Err(format!(
+                        "variadic argument '{}' must be the last argument",
+                        arg.name
+                    ))
+                }
+                Arity::Variadic => (),
+            }
+        }
+
+        for (idx, flag) in self.flags.iter().enumerate() {
+            if flag.long.is_empty() || flag.long.starts_with('-') || flag.long.contains('=') {
+                return Err(format!("flag '{}' is not a valid long name", flag.long));
+            }
+
+            if let Some(s) = flag.short {
+                if s == '-' || s.is_whitespace() {
+                    return Err(format!("flag '{}' has an invalid short name", flag.long));
+                }
+                if self.flags[..idx].iter().any(|f| f.short == Some(s)) {
+                    return Err(format!("short flag '-{}' is declared twice", s));
+                }
+            }
+
+            if let Some(argtype) = flag.argtype {
+                check_default(&flag.long, argtype, &flag.default)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Validate and convert raw words against the specification.
+    pub fn parse(&self, words: &[&str]) -> Result<Args, ArgError> {
+        let mut values: Vec<(String, Vec<ArgValue>)> = self
+            .flags
+            .iter()
+            .map(|f| (f.long.clone(), Vec::new()))
+            .collect();
+        let mut positionals = Vec::new();
+        let mut words = words.iter().copied();
+
+        while let Some(word) = words.next() {
+            if word == "--" {
+                positionals.extend(words.by_ref());
+            } else if let Some(long) = word.strip_prefix("--") {
+                let (name, value) = match long.find('=') {
+                    Some(idx) => (&long[..idx], Some(&long[idx + 1..])),
+                    None => (long, None),
+                };
+                let idx = self
+                    .flags
+                    .iter()
+                    .position(|f| f.long == name)
+                    .ok_or_else(|| ArgError::UnknownFlag(format!("--{}", name)))?;
+                let flag = &self.flags[idx];
+                let v = match (flag.argtype, value) {
+                    (None, None) => ArgValue::Bool(true),
+                    (None, Some(_)) => return Err(ArgError::UnexpectedValue(flag.long.clone())),
+                    (Some(argtype), value) => {
+                        let value = value
+                            .or_else(|| words.next())
+                            .ok_or_else(|| ArgError::MissingValue(flag.long.clone()))?;
+                        convert(&flag.long, argtype, value)?
+                    }
+                };
+                values[idx].1.push(v);
+            } else if word.len() > 1 && word.starts_with('-') && !self.is_negative_number(word) {
+                let shorts = &word[1..];
+                for (i, ch) in shorts.char_indices() {
+                    let idx = self
+                        .flags
+                        .iter()
+                        .position(|f| f.short == Some(ch))
+                        .ok_or_else(|| ArgError::UnknownFlag(format!("-{}", ch)))?;
+                    let flag = &self.flags[idx];
+                    match flag.argtype {
+                        None => values[idx].1.push(ArgValue::Bool(true)),
+                        Some(argtype) => {
+                            // the remainder of the bundle is the value, otherwise the next word
+                            let rest = &shorts[i + ch.len_utf8()..];
+                            let value = if rest.is_empty() {
+                                words
+                                    .next()
+                                    .ok_or_else(|| ArgError::MissingValue(flag.long.clone()))?
+                            } else {
+                                rest
+                            };
+                            values[idx].1.push(convert(&flag.long, argtype, value)?);
+                            break;
+                        }
+                    }
+                }
+            } else {
+                positionals.push(word);
+            }
+        }
+
+        for (flag, (_, v)) in self.flags.iter().zip(values.iter_mut()) {
+            if let (Some(argtype), Some(d), true) = (flag.argtype, &flag.default, v.is_empty()) {
+                v.push(convert(&flag.long, argtype, d)?);
+            }
+        }
+
+        let mut positionals = positionals.into_iter();
+
+        for arg in self.args.iter() {
+            let v = match &arg.arity {
+                Arity::Required => {
+                    let w = positionals
+                        .next()
+                        .ok_or_else(|| ArgError::Missing(arg.name.clone()))?;
+                    vec![convert(&arg.name, arg.argtype, w)?]
+                }
+                Arity::Optional(default) => match positionals.next().or(default.as_deref()) {
+                    Some(w) => vec![convert(&arg.name, arg.argtype, w)?],
+                    None => Vec::new(),
+                },
+                Arity::Variadic => positionals
+                    .by_ref()
+                    .map(|w| convert(&arg.name, arg.argtype, w))
+                    .collect::<Result<_, _>>()?,
+            };
+
+            values.push((arg.name.clone(), v));
+        }
+
+        match positionals.next() {
+            Some(w) => Err(ArgError::Unexpected(w.to_string())),
+            None => Ok(Args {
+                values,
+                flags: self.flags.len(),
+            }),
+        }
+    }
+
+    /// A word such as `-5` or `-0.5` is a value, unless there is a short flag for the first digit.
+    /// The `-` must be followed by a digit or `.`, so words such as `-inf` and `-nan` are flags.
+    fn is_negative_number(&self, word: &str) -> bool {
+        let first = match word[1..].chars().next() {
+            Some(ch) if ch.is_ascii_digit() || ch == '.' => ch,
+            _ => return false,
+        };
+        word.parse::<f64>().is_ok() && !self.flags.iter().any(|f| f.short == Some(first))
+    }
+}
+
+fn convert(name: &str, argtype: ArgType, value: &str) -> Result<ArgValue, ArgError> {
+    argtype.parse(value).ok_or_else(|| ArgError::Invalid {
+        name: name.to_string(),
+        value: value.to_string(),
+        expected: argtype,
+    })
+}
+
+fn check_default(name: &str, argtype: ArgType, default: &Option<String>) -> Result<(), String> {
+    match default {
+        Some(d) if argtype.parse(d).is_none() => Err(format!(
+            "default '{}' of argument '{}' is not a valid {}",
+            d, name, argtype
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// The converted arguments passed to an action.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Args {
+    /// The values of the flags and options, followed by the positional arguments.
+    values: Vec<(String, Vec<ArgValue>)>,
+    /// The number of flags and options at the start of `values`.
+    flags: usize,
+}
+
+impl Args {
+    /// The value of the argument `name`.
+    /// For variadic arguments this is the first value, and for a repeated option the last value given.
+    pub fn get(&self, name: &str) -> Option<&ArgValue> {
+        let idx = self.position(name)?;
+        let values = &self.values[idx].1;
+        if idx < self.flags {
+            values.last()
+        } else {
+            values.first()
+        }
+    }
+
+    /// All values of the argument `name`, in the order given.
+    /// Empty if the argument was not supplied or does not exist.
+    pub fn get_all(&self, name: &str) -> &[ArgValue] {
+        self.position(name)
+            .map(|idx| self.values[idx].1.as_slice())
+            .unwrap_or(&[])
+    }
+
+    fn position(&self, name: &str) -> Option<usize> {
+        self.values.iter().position(|(n, _)| n == name)
+    }
+
+    /// The string value of the argument `name`.
+    pub fn get_str(&self, name: &str) -> Option<&str> {
+        self.get(name).and_then(ArgValue::as_str)
+    }
+
+    /// The integer value of the argument `name`.
+    pub fn get_int(&self, name: &str) -> Option<i64> {
+        self.get(name).and_then(ArgValue::as_int)
+    }
+
+    /// The numeric value of the argument `name`.
+    pub fn get_float(&self, name: &str) -> Option<f64> {
+        self.get(name).and_then(ArgValue::as_float)
+    }
+
+    /// The boolean value of the argument `name`.
+    pub fn get_bool(&self, name: &str) -> Option<bool> {
+        self.get(name).and_then(ArgValue::as_bool)
+    }
+
+    /// Returns `true` if the argument `name` has a value.
+    pub fn contains(&self, name: &str) -> bool {
+        !self.get_all(name).is_empty()
+    }
+
+    /// Returns `true` if the switch `name` was supplied.
+    pub fn flag(&self, name: &str) -> bool {
+        self.contains(name)
+    }
+
+    /// The number of times the switch or option `name` was supplied, such as `-vvv`.
+    pub fn occurrences(&self, name: &str) -> usize {
+        self.get_all(name).len()
+    }
+}
+
+/// Error variants when validating arguments against an [`ArgSpec`].
+#[derive(Debug, PartialEq, Clone)]
+pub enum ArgError {
+    /// A required argument was not supplied.
+    Missing(String),
+    /// More values were supplied than the specification accepts.
+    Unexpected(String),
+    /// A flag or option that is not in the specification was supplied.
+    UnknownFlag(String),
+    /// An option was supplied without a value.
+    MissingValue(String),
+    /// A switch was supplied with a value, such as `--verbose=yes`.
+    UnexpectedValue(String),
+    /// A value could not be converted to the argument's type.
+    Invalid {
+        /// The argument name.
+        name: String,
+        /// The supplied value.
+        value: String,
+        /// The type the value should convert to.
+        expected: ArgType,
+    },
+}
+
+impl fmt::Display for ArgError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ArgError::Missing(name) => write!(f, "missing argument '{}'", name),
+            ArgError::Unexpected(value) => write!(f, "unexpected argument '{}'", value),
+            ArgError::UnknownFlag(flag) => write!(f, "unknown flag '{}'", flag),
+            ArgError::MissingValue(name) => write!(f, "option '--{}' requires a value", name),
+            ArgError::UnexpectedValue(name) => write!(f, "flag '--{}' does not take a value", name),
+            ArgError::Invalid {
+                name,
+                value,
+                expected,
+            } => write!(
+                f,
+                "'{}' is not a valid {} for argument '{}'",
+                value, expected, name
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ArgError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn spec() -> ArgSpec {
+        ArgSpec::new()
+            .required("n", ArgType::Int)
+            .optional("ratio", ArgType::Float)
+            .with_default("verbose", ArgType::Bool, "no")
+            .variadic("rest", ArgType::Str)
+    }
+
+    #[test]
+    fn usage_test() {
+        assert_eq!(spec().usage(), "<n> [ratio] [verbose=no] [rest...]");
+        assert_eq!(ArgSpec::new().usage(), "");
+    }
+
+    #[test]
+    fn validate_test() {
+        assert_eq!(spec().validate(), Ok(()));
+        assert!(ArgSpec::new()
+            .optional("a", ArgType::Str)
+            .required("b", ArgType::Str)
+            .validate()
+            .is_err());
+        assert!(ArgSpec::new()
+            .variadic("a", ArgType::Str)
+            .optional("b", ArgType::Str)
+            .validate()
+            .is_err());
+        assert!(ArgSpec::new()
+            .required("a", ArgType::Str)
+            .required("a", ArgType::Str)
+            .validate()
+            .is_err());
+        assert!(ArgSpec::new()
+            .with_default("a", ArgType::Int, "x")
+            .validate()
+            .is_err());
+        assert!(ArgSpec::new()
+            .required("a", ArgType::Str)
+            .flag("a", None)
+            .validate()
+            .is_err());
+        assert!(ArgSpec::new()
+            .flag("a", Some('x'))
+            .flag("b", Some('x'))
+            .validate()
+            .is_err());
+        assert!(ArgSpec::new().flag("a=b", None).validate().is_err());
+        assert!(ArgSpec::new()
+            .option_with_default("a", None, ArgType::Float, "x")
+            .validate()
+            .is_err());
+    }
+
+    fn flag_spec() -> ArgSpec {
+        ArgSpec::new()
+            .flag("verbose", Some('v'))
+            .flag("quiet", Some('q'))
+            .flag("all", None)
+            .option("out", Some('o'), ArgType::Str)
+            .option_with_default("count", Some('n'), ArgType::Int, "1")
+            .variadic("rest", ArgType::Float)
+    }
+
+    #[test]
+    fn flag_usage_test() {
+        assert_eq!(
+            flag_spec().usage(),
+            "[-v|--verbose] [-q|--quiet] [--all] [-o|--out <out>] [-n|--count <count=1>] [rest...]"
+        );
+    }
+
+    #[test]
+    fn parse_flags_test() {
+        let args = flag_spec().parse(&[]).unwrap();
+        assert!(!args.flag("verbose"));
+        assert_eq!(args.get_str("out"), None);
+        assert_eq!(args.get_int("count"), Some(1));
+
+        let args = flag_spec()
+            .parse(&["1", "--verbose", "-qv", "--out=a.txt", "-n5", "2"])
+            .unwrap();
+        assert!(args.flag("verbose"));
+        assert_eq!(args.occurrences("verbose"), 2);
+        assert!(args.flag("quiet"));
+        assert!(!args.flag("all"));
+        assert_eq!(args.get_str("out"), Some("a.txt"));
+        assert_eq!(args.get_int("count"), Some(5));
+        assert_eq!(
+            args.get_all("rest"),
+            &[ArgValue::Float(1.0), ArgValue::Float(2.0)]
+        );
+
+        let args = flag_spec().parse(&["--out", "b", "-vn", "3", "-1.5", "--", "-v", "--all"]);
+        assert_eq!(
+            args,
+            Err(ArgError::Invalid {
+                name: "rest".to_string(),
+                value: "-v".to_string(),
+                expected: ArgType::Float
+            })
+        );
+        let args = flag_spec()
+            .parse(&["--out", "b", "-vn", "3", "-1.5", "--", "-2"])
+            .unwrap();
+        assert_eq!(args.get_str("out"), Some("b"));
+        assert_eq!(args.get_int("count"), Some(3));
+        assert_eq!(
+            args.get_all("rest"),
+            &[ArgValue::Float(-1.5), ArgValue::Float(-2.0)]
+        );
+        assert_eq!(args.get_float("rest"), Some(-1.5));
+
+        // the last value of a repeated option wins
+        let args = flag_spec()
+            .parse(&["-n", "2", "--count=3", "-o", "a", "-ob"])
+            .unwrap();
+        assert_eq!(args.get_int("count"), Some(3));
+        assert_eq!(args.get_str("out"), Some("b"));
+        assert_eq!(args.occurrences("out"), 2);
+        assert_eq!(args.get_all("count"), &[ArgValue::Int(2), ArgValue::Int(3)]);
+
+        assert_eq!(
+            flag_spec().parse(&["--nope"]),
+            Err(ArgError::UnknownFlag("--nope".to_string()))
+        );
+        assert_eq!(
+            flag_spec().parse(&["-vx"]),
+            Err(ArgError::UnknownFlag("-x".to_string()))
+        );
+        // only a digit or `.` after the `-` makes a negative number
+        assert_eq!(
+            flag_spec().parse(&["-.5"]).unwrap().get_float("rest"),
+            Some(-0.5)
+        );
+        assert_eq!(
+            flag_spec().parse(&["-inf"]),
+            Err(ArgError::UnknownFlag("-i".to_string()))
+        );
+        assert_eq!(
+            flag_spec().parse(&["-nan"]),
+            Err(ArgError::Invalid {
+                name: "count".to_string(),
+                value: "an".to_string(),
+                expected: ArgType::Int
+            })
+        );
+        assert_eq!(
+            flag_spec().parse(&["--out"]),
+            Err(ArgError::MissingValue("out".to_string()))
+        );
+        assert_eq!(
+            flag_spec().parse(&["--all=yes"]),
+            Err(ArgError::UnexpectedValue("all".to_string()))
+        );
+        assert_eq!(
+            flag_spec().parse(&["-n", "x"]),
+            Err(ArgError::Invalid {
+                name: "count".to_string(),
+                value: "x".to_string(),
+                expected: ArgType::Int
+            })
+        );
+    }
+
+    #[test]
+    fn parse_test() {
+        let args = spec().parse(&["5"]).unwrap();
+        assert_eq!(args.get_int("n"), Some(5));
+        assert_eq!(args.get("ratio"), None);
+        assert_eq!(args.get_bool("verbose"), Some(false));
+        assert_eq!(args.get_all("rest"), &[]);
+
+        let args = spec().parse(&["-2", "1.5", "on", "a", "b"]).unwrap();
+        assert_eq!(args.get_int("n"), Some(-2));
+        assert_eq!(args.get_float("ratio"), Some(1.5));
+        assert_eq!(args.get_bool("verbose"), Some(true));
+        assert_eq!(
+            args.get_all("rest"),
+            &[
+                ArgValue::Str("a".to_string()),
+                ArgValue::Str("b".to_string())
+            ]
+        );
+
+        assert_eq!(spec().parse(&[]), Err(ArgError::Missing("n".to_string())));
+        assert_eq!(
+            spec().parse(&["1", "x"]),
+            Err(ArgError::Invalid {
+                name: "ratio".to_string(),
+                value: "x".to_string(),
+                expected: ArgType::Float
+            })
+        );
+        assert_eq!(
+            ArgSpec::new().parse(&["a"]),
+            Err(ArgError::Unexpected("a".to_string()))
+        );
+    }
+
+    #[test]
+    fn int_range_test() {
+        let spec = ArgSpec::new().required("n", ArgType::IntRange(0, 255));
+        assert_eq!(spec.parse(&["255"]).unwrap().get_int("n"), Some(255));
+
+        let err = spec.parse(&["-1"]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "'-1' is not a valid integer from 0 to 255 for argument 'n'"
+        );
+    }
+
+    #[test]
+    fn float32_test() {
+        let spec = ArgSpec::new().required("x", ArgType::Float32);
+        assert_eq!(spec.parse(&["3e38"]).unwrap().get_float("x"), Some(3e38));
+        assert_eq!(
+            spec.parse(&["inf"]).unwrap().get_float("x"),
+            Some(f64::INFINITY)
+        );
+
+        let err = spec.parse(&["1e300"]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "'1e300' is not a valid 32-bit number for argument 'x'"
+        );
+    }
+}
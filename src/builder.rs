@@ -18,33 +18,172 @@
 //!     .into_commander().unwrap();
 //! ```
 use super::*;
+use args::{ArgSpec, Args};
 
 /// The persistent `Builder` structure to construct a `Commander` command tree.
 /// See module level documentation for more information.
 #[derive(Debug, PartialEq)]
-pub struct Builder<R> {
-    parents: Vec<SubClass<R>>,
-    current: SubClass<R>,
+pub struct Builder<R, S = ()> {
+    parents: Vec<SubClass<R, S>>,
+    current: SubClass<R, S>,
+    builtins: Builtins,
+    state: S,
 }
 
 /// The common functions across a `Builder` or a `BuilderResult`.
 /// See module level documentation for more information.
-pub trait BuilderChain<R> {
+pub trait BuilderChain<R, S = ()> {
     /// Start a new nested class. If the name already exists a `BuildError` will be returned.
-    fn begin_class<H: Into<CmdStr>>(self, name: &str, help_msg: H) -> BuilderResult<R>;
+    fn begin_class<H: Into<CmdStr>>(self, name: &str, help_msg: H) -> BuilderResult<R, S>;
     /// Close a class and move to it's parent.
     /// If no parent exists (this function is called on the root), a `BuildError` will be returned.
-    fn end_class(self) -> BuilderResult<R>;
+    fn end_class(self) -> BuilderResult<R, S>;
     /// Add an action. The closure type gives the arguments after the action command as an array of strings.
     fn add_action<H: Into<CmdStr>, F: FnMut(&mut dyn Write, &[&str]) -> R + Send + 'static>(
         self,
         name: &str,
         help_msg: H,
         closure: F,
-    ) -> BuilderResult<R>;
+    ) -> BuilderResult<R, S>;
+    /// Add an action which receives the user state. The closure type gives the user state, and the arguments after the action command as an array of strings.
+    fn add_state_action<H, F>(self, name: &str, help_msg: H, closure: F) -> BuilderResult<R, S>
+    where
+        H: Into<CmdStr>,
+        F: FnMut(&mut S, &mut dyn Write, &[&str]) -> R + Send + 'static;
+    /// Add an action which receives an [`ActionContext`].
+    /// The context exposes the writer, arguments and user state, along with the invocation path, the raw input line, and whether output should be colourised.
+    fn add_context_action<H, F>(self, name: &str, help_msg: H, closure: F) -> BuilderResult<R, S>
+    where
+        H: Into<CmdStr>,
+        F: FnMut(&mut ActionContext<S>) -> R + Send + 'static;
+    /// Add a fallible action. The closure type gives the arguments after the action command as an array of strings.
+    ///
+    /// If the closure returns an error, the error is written and `LineResult::ActionError` is returned from `parse_line`.
+    fn add_fallible_action<H, F, E>(
+        self,
+        name: &str,
+        help_msg: H,
+        closure: F,
+    ) -> BuilderResult<R, S>
+    where
+        H: Into<CmdStr>,
+        F: FnMut(&mut dyn Write, &[&str]) -> Result<R, E> + Send + 'static,
+        E: Into<Box<dyn std::error::Error + Send + Sync>>;
+    /// Add an action with a typed argument specification.
+    ///
+    /// The arguments after the action command are validated and converted against `args` before the closure is invoked.
+    /// If they do not match, a usage error is written instead and `LineResult::InvalidArgs` is returned from `parse_line`.
+    /// If the specification is malformed a `BuildError::InvalidArgSpec` will be returned.
+    fn add_action_with_args<H, F>(
+        self,
+        name: &str,
+        help_msg: H,
+        args: ArgSpec,
+        closure: F,
+    ) -> BuilderResult<R, S>
+    where
+        H: Into<CmdStr>,
+        F: FnMut(&mut dyn Write, &Args) -> R + Send + 'static;
+    /// Add a fallible action with a typed argument specification which receives an [`ActionContext`].
+    ///
+    /// This composes the other forms: the arguments are validated against `args` as with [`add_action_with_args`](BuilderChain::add_action_with_args)
+    /// and available through [`ActionContext::parsed_args`], the context gives the user state,
+    /// and an error returned from the closure is handled as with [`add_fallible_action`](BuilderChain::add_fallible_action).
+    ///
+    /// # Example
+    /// ```rust
+    /// use cmdtree::*;
+    /// use cmdtree::args::{ArgSpec, ArgType};
+    ///
+    /// let mut cmder = Builder::with_state("base", 0i64)
+    ///     .add_context_action_with_args(
+    ///         "add",
+    ///         "adds to the total",
+    ///         ArgSpec::new().required("n", ArgType::Int),
+    ///         |ctx| {
+    ///             let n = ctx.parsed_args().unwrap().get_int("n").unwrap();
+    ///             let total = ctx.state_mut();
+    ///             *total = total.checked_add(n).ok_or("total overflowed")?;
+    ///             Ok::<_, &str>(*total)
+    ///         },
+    ///     )
+    ///     .into_commander().unwrap();
+    ///
+    /// assert_eq!(cmder.parse_line("add 2", false, &mut std::io::sink()), LineResult::Action(2));
+    /// assert_eq!(cmder.parse_line("add 3", false, &mut std::io::sink()), LineResult::Action(5));
+    /// ```
+    fn add_context_action_with_args<H, F, E>(
+        self,
+        name: &str,
+        help_msg: H,
+        args: ArgSpec,
+        closure: F,
+    ) -> BuilderResult<R, S>
+    where
+        H: Into<CmdStr>,
+        F: FnMut(&mut ActionContext<S>) -> Result<R, E> + Send + 'static,
+        E: Into<Box<dyn std::error::Error + Send + Sync>>;
+    /// Attach an argument completer to an action in the current class.
+    ///
+    /// The completer receives the `Commander` state, the arguments before the word being completed,
+    /// and the (possibly empty) word itself, and returns the candidates for the word.
+    /// It is used by [`TreeCompleter`](crate::completion::TreeCompleter) when the line is inside the action's arguments.
+    /// The action can be given by its name or an alias.
+    /// If no action with the name exists in the current class, a `BuildError::NameNotFound` will be returned.
+    ///
+    /// # Example
+    /// ```rust
+    /// use cmdtree::*;
+    /// use cmdtree::completion::TreeCompleter;
+    ///
+    /// let colours = vec!["red".to_string(), "green".to_string(), "blue".to_string()];
+    ///
+    /// let cmder = Builder::<(), _>::with_state("base", colours)
+    ///     .add_action("colour", "", |_, _| ())
+    ///     .add_arg_completer("colour", |colours, _args, word| {
+    ///         colours.iter()
+    ///             .filter(|x| x.starts_with(word))
+    ///             .cloned()
+    ///             .collect()
+    ///     })
+    ///     .into_commander().unwrap();
+    ///
+    /// let completer = TreeCompleter::new(&cmder);
+    /// assert_eq!(completer.completions("colour "), vec!["red", "green", "blue"]);
+    /// assert_eq!(completer.completions("colour red g"), vec!["green"]);
+    /// ```
+    fn add_arg_completer<F>(self, action: &str, completer: F) -> BuilderResult<R, S>
+    where
+        F: Fn(&S, &[&str], &str) -> Vec<String> + Send + Sync + 'static;
+
+    /// Add an alias of a class or action in the current class.
+    ///
+    /// The alias is matched as the name is, and is shown alongside the name in help messages.
+    /// If the alias already exists a `BuildError` will be returned,
+    /// and if no class or action with the name exists in the current class a `BuildError::NameNotFound` will be returned.
+    ///
+    /// # Example
+    /// ```rust
+    /// use cmdtree::*;
+    ///
+    /// let mut cmder = Builder::default_config("base")
+    ///     .add_action("list", "", |_, _| ())
+    ///     .add_alias("list", "ls")
+    ///     .into_commander().unwrap();
+    ///
+    /// assert_eq!(cmder.parse_line("ls", false, &mut std::io::sink()), LineResult::Action(()));
+    /// ```
+    fn add_alias(self, name: &str, alias: &str) -> BuilderResult<R, S>;
+
+    /// Configure the builtin commands, see the [`builtins` module](crate::builtins).
+    ///
+    /// The class and action names already added are checked against the builtin names, as are names added afterwards.
+    /// If two builtins share a name a `BuildError::DuplicateBuiltin` will be returned, and if a name is empty,
+    /// or contains whitespace or a `.` other than a name of only `.`s such as `..`, a `BuildError::InvalidBuiltinName`.
+    fn with_builtins(self, builtins: Builtins) -> BuilderResult<R, S>;
 
     /// Navigates to the root class, closing out the classes as it goes.
-    fn root(self) -> BuilderResult<R>;
+    fn root(self) -> BuilderResult<R, S>;
 
     /// Finishes the construction of the command tree and returns the build `Commander`.
     ///
@@ -54,11 +193,11 @@ pub trait BuilderChain<R> {
     ///
     /// If an error is propogating through then the function will error. If there was no error (ie `into_commander` was called on a `Builder` instance)
     /// then this function should not fail.
-    fn into_commander(self) -> Result<Commander<R>, BuildError>;
+    fn into_commander(self) -> Result<Commander<R, S>, BuildError>;
 }
 
 /// The common result of `BuilderChain` functions.
-pub type BuilderResult<R> = Result<Builder<R>, BuildError>;
+pub type BuilderResult<R, S = ()> = Result<Builder<R, S>, BuildError>;
 
 impl Builder<()> {
     /// Initialise a `Builder` instance with the given root name.
@@ -70,30 +209,41 @@ impl Builder<()> {
 impl<R> Builder<R> {
     /// Initialise new `Builder` instance with no configuration.
     pub fn new(root_name: &str) -> Self {
+        Builder::with_state(root_name, ())
+    }
+}
+
+impl<R, S> Builder<R, S> {
+    /// Initialise new `Builder` instance with a user state.
+    ///
+    /// The state is owned by the built `Commander`, and passed to actions added with `add_state_action`.
+    pub fn with_state(root_name: &str, state: S) -> Self {
         Builder {
             parents: Vec::new(),
             current: SubClass::with_name(root_name, "base class of commander tree"),
+            builtins: Builtins::default(),
+            state,
         }
     }
 }
 
-impl<R> BuilderChain<R> for Builder<R> {
-    fn begin_class<H: Into<CmdStr>>(mut self, name: &str, help_msg: H) -> BuilderResult<R> {
-        check_names(name, &self.current).map(|_| {
+impl<R, S> BuilderChain<R, S> for Builder<R, S> {
+    fn begin_class<H: Into<CmdStr>>(mut self, name: &str, help_msg: H) -> BuilderResult<R, S> {
+        check_names(name, &self.current, &self.builtins).map(|_| {
             self.parents.push(self.current);
             self.current = SubClass::with_name(name, help_msg);
             self
         })
     }
 
-    fn end_class(mut self) -> BuilderResult<R> {
+    fn end_class(mut self) -> BuilderResult<R, S> {
         let mut parent = self.parents.pop().ok_or(BuildError::NoParent)?;
         parent.classes.push(Arc::new(self.current)); // push the child class onto the parent's classes vector
         self.current = parent;
         Ok(self)
     }
 
-    fn root(self) -> BuilderResult<R> {
+    fn root(self) -> BuilderResult<R, S> {
         let mut root = self;
         while !root.parents.is_empty() {
             root = root.end_class().expect("shouldn't dip below zero parents");
@@ -101,46 +251,244 @@ impl<R> BuilderChain<R> for Builder<R> {
         Ok(root)
     }
 
-    fn add_action<H, F>(mut self, name: &str, help_msg: H, closure: F) -> BuilderResult<R>
+    fn add_action<H, F>(mut self, name: &str, help_msg: H, mut closure: F) -> BuilderResult<R, S>
     where
         H: Into<CmdStr>,
         F: FnMut(&mut dyn Write, &[&str]) -> R + Send + 'static,
     {
-        check_names(name, &self.current).map(|_| {
+        check_names(name, &self.current, &self.builtins).map(|_| {
             self.current.actions.push(Action {
                 name: name.to_lowercase(),
+                aliases: Vec::new(),
                 help: help_msg.into(),
-                closure: Mutex::new(Box::new(closure)),
+                args: None,
+                completer: None,
+                closure: Mutex::new(Box::new(move |ctx| Ok(closure(ctx.writer, ctx.args)))),
             });
             self
         })
     }
 
-    fn into_commander<'c>(self) -> Result<Commander<R>, BuildError> {
+    fn add_state_action<H, F>(
+        mut self,
+        name: &str,
+        help_msg: H,
+        mut closure: F,
+    ) -> BuilderResult<R, S>
+    where
+        H: Into<CmdStr>,
+        F: FnMut(&mut S, &mut dyn Write, &[&str]) -> R + Send + 'static,
+    {
+        check_names(name, &self.current, &self.builtins).map(|_| {
+            self.current.actions.push(Action {
+                name: name.to_lowercase(),
+                aliases: Vec::new(),
+                help: help_msg.into(),
+                args: None,
+                completer: None,
+                closure: Mutex::new(Box::new(move |ctx| {
+                    Ok(closure(ctx.state, ctx.writer, ctx.args))
+                })),
+            });
+            self
+        })
+    }
+
+    fn add_context_action<H, F>(
+        mut self,
+        name: &str,
+        help_msg: H,
+        mut closure: F,
+    ) -> BuilderResult<R, S>
+    where
+        H: Into<CmdStr>,
+        F: FnMut(&mut ActionContext<S>) -> R + Send + 'static,
+    {
+        check_names(name, &self.current, &self.builtins).map(|_| {
+            self.current.actions.push(Action {
+                name: name.to_lowercase(),
+                aliases: Vec::new(),
+                help: help_msg.into(),
+                args: None,
+                completer: None,
+                closure: Mutex::new(Box::new(move |ctx| Ok(closure(ctx)))),
+            });
+            self
+        })
+    }
+
+    fn add_fallible_action<H, F, E>(
+        mut self,
+        name: &str,
+        help_msg: H,
+        mut closure: F,
+    ) -> BuilderResult<R, S>
+    where
+        H: Into<CmdStr>,
+        F: FnMut(&mut dyn Write, &[&str]) -> Result<R, E> + Send + 'static,
+        E: Into<Box<dyn std::error::Error + Send + Sync>>,
+    {
+        check_names(name, &self.current, &self.builtins).map(|_| {
+            self.current.actions.push(Action {
+                name: name.to_lowercase(),
+                aliases: Vec::new(),
+                help: help_msg.into(),
+                args: None,
+                completer: None,
+                closure: Mutex::new(Box::new(move |ctx| {
+                    closure(ctx.writer, ctx.args)
+                        .map_err(|e| CallError::Action(ActionError::new(e)))
+                })),
+            });
+            self
+        })
+    }
+
+    fn add_action_with_args<H, F>(
+        mut self,
+        name: &str,
+        help_msg: H,
+        args: ArgSpec,
+        mut closure: F,
+    ) -> BuilderResult<R, S>
+    where
+        H: Into<CmdStr>,
+        F: FnMut(&mut dyn Write, &Args) -> R + Send + 'static,
+    {
+        check_names(name, &self.current, &self.builtins)?;
+        args.validate().map_err(BuildError::InvalidArgSpec)?;
+        self.current.actions.push(Action {
+            name: name.to_lowercase(),
+            aliases: Vec::new(),
+            help: help_msg.into(),
+            args: Some(args),
+            completer: None,
+            closure: Mutex::new(Box::new(move |ctx| {
+                let args = ctx
+                    .parsed
+                    .as_ref()
+                    .expect("arguments are parsed before the call");
+                Ok(closure(ctx.writer, args))
+            })),
+        });
+        Ok(self)
+    }
+
+    fn add_context_action_with_args<H, F, E>(
+        mut self,
+        name: &str,
+        help_msg: H,
+        args: ArgSpec,
+        mut closure: F,
+    ) -> BuilderResult<R, S>
+    where
+        H: Into<CmdStr>,
+        F: FnMut(&mut ActionContext<S>) -> Result<R, E> + Send + 'static,
+        E: Into<Box<dyn std::error::Error + Send + Sync>>,
+    {
+        check_names(name, &self.current, &self.builtins)?;
+        args.validate().map_err(BuildError::InvalidArgSpec)?;
+        self.current.actions.push(Action {
+            name: name.to_lowercase(),
+            aliases: Vec::new(),
+            help: help_msg.into(),
+            args: Some(args),
+            completer: None,
+            closure: Mutex::new(Box::new(move |ctx| {
+                closure(ctx).map_err(|e| CallError::Action(ActionError::new(e)))
+            })),
+        });
+        Ok(self)
+    }
+
+    fn add_arg_completer<F>(mut self, action: &str, completer: F) -> BuilderResult<R, S>
+    where
+        F: Fn(&S, &[&str], &str) -> Vec<String> + Send + Sync + 'static,
+    {
+        let lwr = action.to_lowercase();
+        match self
+            .current
+            .actions
+            .iter_mut()
+            .find(|a| a.names().any(|n| n == lwr))
+        {
+            Some(a) => {
+                a.completer = Some(Arc::new(completer));
+                Ok(self)
+            }
+            None => Err(BuildError::NameNotFound(lwr)),
+        }
+    }
+
+    fn add_alias(mut self, name: &str, alias: &str) -> BuilderResult<R, S> {
+        check_names(alias, &self.current, &self.builtins)?;
+        let lwr = name.to_lowercase();
+        let alias = alias.to_lowercase();
+
+        if let Some(a) = self.current.actions.iter_mut().find(|a| a.name == lwr) {
+            a.aliases.push(alias);
+        } else if let Some(c) = self.current.classes.iter_mut().find(|c| c.name == lwr) {
+            Arc::get_mut(c)
+                .expect("classes are not shared while building")
+                .aliases
+                .push(alias);
+        } else {
+            return Err(BuildError::NameNotFound(lwr));
+        }
+
+        Ok(self)
+    }
+
+    fn with_builtins(mut self, builtins: Builtins) -> BuilderResult<R, S> {
+        if let Some(name) = builtins.duplicate() {
+            return Err(BuildError::DuplicateBuiltin(name.to_string()));
+        }
+        if let Some(name) = builtins.invalid() {
+            return Err(BuildError::InvalidBuiltinName(name.to_string()));
+        }
+
+        for class in self.parents.iter().chain(std::iter::once(&self.current)) {
+            class.check_tree(|n| builtins.find(n).is_some())?;
+        }
+
+        self.builtins = builtins;
+        Ok(self)
+    }
+
+    fn into_commander<'c>(self) -> Result<Commander<R, S>, BuildError> {
         let root = self.root()?;
         let rc = Arc::new(root.current);
         Ok(Commander {
             root: Arc::clone(&rc),
             current: Arc::clone(&rc),
+            parents: Vec::new(),
+            back_history: VecDeque::new(),
+            forward_history: Vec::new(),
+            dir_stack: VecDeque::new(),
             path: rc.name.to_string(),
+            error_chain: false,
+            abbreviations: false,
+            builtins: root.builtins,
+            runtime_aliases: BTreeMap::new(),
+            state: Arc::new(RwLock::new(root.state)),
         })
     }
 }
 
-impl<R> BuilderChain<R> for BuilderResult<R> {
-    fn begin_class<H: Into<CmdStr>>(self, name: &str, help_msg: H) -> BuilderResult<R> {
+impl<R, S> BuilderChain<R, S> for BuilderResult<R, S> {
+    fn begin_class<H: Into<CmdStr>>(self, name: &str, help_msg: H) -> BuilderResult<R, S> {
         self?.begin_class(name, help_msg)
     }
 
-    fn end_class(self) -> BuilderResult<R> {
+    fn end_class(self) -> BuilderResult<R, S> {
         self?.end_class()
     }
 
-    fn root(self) -> BuilderResult<R> {
+    fn root(self) -> BuilderResult<R, S> {
         self?.root()
     }
 
-    fn add_action<H, F>(self, name: &str, help_msg: H, closure: F) -> BuilderResult<R>
+    fn add_action<H, F>(self, name: &str, help_msg: H, closure: F) -> BuilderResult<R, S>
     where
         H: Into<CmdStr>,
         F: FnMut(&mut dyn Write, &[&str]) -> R + Send + 'static,
@@ -148,22 +496,97 @@ impl<R> BuilderChain<R> for BuilderResult<R> {
         self?.add_action(name, help_msg, closure)
     }
 
-    fn into_commander<'c>(self) -> Result<Commander<R>, BuildError> {
+    fn add_state_action<H, F>(self, name: &str, help_msg: H, closure: F) -> BuilderResult<R, S>
+    where
+        H: Into<CmdStr>,
+        F: FnMut(&mut S, &mut dyn Write, &[&str]) -> R + Send + 'static,
+    {
+        self?.add_state_action(name, help_msg, closure)
+    }
+
+    fn add_context_action<H, F>(self, name: &str, help_msg: H, closure: F) -> BuilderResult<R, S>
+    where
+        H: Into<CmdStr>,
+        F: FnMut(&mut ActionContext<S>) -> R + Send + 'static,
+    {
+        self?.add_context_action(name, help_msg, closure)
+    }
+
+    fn add_fallible_action<H, F, E>(
+        self,
+        name: &str,
+        help_msg: H,
+        closure: F,
+    ) -> BuilderResult<R, S>
+    where
+        H: Into<CmdStr>,
+        F: FnMut(&mut dyn Write, &[&str]) -> Result<R, E> + Send + 'static,
+        E: Into<Box<dyn std::error::Error + Send + Sync>>,
+    {
+        self?.add_fallible_action(name, help_msg, closure)
+    }
+
+    fn add_action_with_args<H, F>(
+        self,
+        name: &str,
+        help_msg: H,
+        args: ArgSpec,
+        closure: F,
+    ) -> BuilderResult<R, S>
+    where
+        H: Into<CmdStr>,
+        F: FnMut(&mut dyn Write, &Args) -> R + Send + 'static,
+    {
+        self?.add_action_with_args(name, help_msg, args, closure)
+    }
+
+    fn add_context_action_with_args<H, F, E>(
+        self,
+        name: &str,
+        help_msg: H,
+        args: ArgSpec,
+        closure: F,
+    ) -> BuilderResult<R, S>
+    where
+        H: Into<CmdStr>,
+        F: FnMut(&mut ActionContext<S>) -> Result<R, E> + Send + 'static,
+        E: Into<Box<dyn std::error::Error + Send + Sync>>,
+    {
+        self?.add_context_action_with_args(name, help_msg, args, closure)
+    }
+
+    fn add_arg_completer<F>(self, action: &str, completer: F) -> BuilderResult<R, S>
+    where
+        F: Fn(&S, &[&str], &str) -> Vec<String> + Send + Sync + 'static,
+    {
+        self?.add_arg_completer(action, completer)
+    }
+
+    fn add_alias(self, name: &str, alias: &str) -> BuilderResult<R, S> {
+        self?.add_alias(name, alias)
+    }
+
+    fn with_builtins(self, builtins: Builtins) -> BuilderResult<R, S> {
+        self?.with_builtins(builtins)
+    }
+
+    fn into_commander<'c>(self) -> Result<Commander<R, S>, BuildError> {
         self?.into_commander()
     }
 }
 
-fn check_names<R>(name: &str, subclass: &SubClass<R>) -> Result<(), BuildError> {
+fn check_names<R, S>(
+    name: &str,
+    subclass: &SubClass<R, S>,
+    builtins: &Builtins,
+) -> Result<(), BuildError> {
     let lwr = name.to_lowercase();
     // check names
-    if lwr == "help"
-        || lwr == "cancel"
-        || lwr == "c"
-        || lwr == "exit"
-        || subclass.actions.iter().any(|x| x.name == lwr)
-    {
+    if builtins.find(&lwr).is_some() {
+        Err(BuildError::NameIsBuiltin(lwr))
+    } else if subclass.actions.iter().any(|x| x.names().any(|n| n == lwr)) {
         Err(BuildError::NameExistsAsAction)
-    } else if subclass.classes.iter().any(|x| x.name == lwr) {
+    } else if subclass.classes.iter().any(|x| x.names().any(|n| n == lwr)) {
         Err(BuildError::NameExistsAsClass)
     } else {
         Ok(())
@@ -180,6 +603,30 @@ pub enum BuildError {
     /// Tried to get to a parent when none exists.
     /// This usually occurs when `end_class` is called too many times.
     NoParent,
+    /// An action's argument specification is malformed.
+    /// The inner data describes the problem.
+    InvalidArgSpec(String),
+    /// The name does not exist in the current class.
+    /// The inner data is the name.
+    NameNotFound(String),
+    /// Two builtin commands share a name.
+    /// The inner data is the name.
+    DuplicateBuiltin(String),
+    /// A builtin name is empty, or contains whitespace or a `.`, so it cannot be entered as a single word.
+    /// The inner data is the name.
+    InvalidBuiltinName(String),
+    /// The name is taken by an enabled builtin command.
+    /// The inner data is the name.
+    NameIsBuiltin(String),
+    /// A definition could not be read, or the top class of a definition has aliases.
+    /// The inner data describes the problem.
+    InvalidDefinition(String),
+    /// An action of a definition has no handler.
+    /// The inner data is the handler path.
+    MissingHandler(String),
+    /// A handler was not bound to any action of a definition.
+    /// The inner data is the handler path.
+    UnboundHandler(String),
 }
 
 #[cfg(test)]
@@ -188,30 +635,150 @@ mod tests {
 
     #[test]
     fn check_names_test() {
-        let mut sc = SubClass::with_name("name", "adsf");
-        assert_eq!(check_names("name1", &sc), Ok(()));
+        let mut sc = SubClass::<(), ()>::with_name("name", "adsf");
+        assert_eq!(check_names("name1", &sc, &Builtins::default()), Ok(()));
         sc.classes
             .push(Arc::new(SubClass::with_name("sub-name", "asdf")));
-        assert_eq!(check_names("name1", &sc), Ok(()));
+        assert_eq!(check_names("name1", &sc, &Builtins::default()), Ok(()));
         assert_eq!(
-            check_names("sub-name", &sc),
+            check_names("sub-name", &sc, &Builtins::default()),
             Err(BuildError::NameExistsAsClass)
         );
         sc.actions.push(Action {
             name: "name1".to_string(),
+            aliases: Vec::new(),
             help: "adf".into(),
-            closure: Mutex::new(Box::new(|_, _| ())),
+            args: None,
+            completer: None,
+            closure: Mutex::new(Box::new(|_| Ok(()))),
         });
         assert_eq!(
-            check_names("name1", &sc),
+            check_names("name1", &sc, &Builtins::default()),
             Err(BuildError::NameExistsAsAction)
         );
+        assert_eq!(
+            check_names("Help", &sc, &Builtins::default()),
+            Err(BuildError::NameIsBuiltin("help".to_string()))
+        );
     }
 
     #[test]
     fn no_help_cancel_or_exit_classes() {
         let cmdr = Builder::default_config("adf").begin_class("help", "shouldn't work");
+        assert_eq!(cmdr, Err(BuildError::NameIsBuiltin("help".to_string())));
+    }
+
+    #[test]
+    fn invalid_arg_spec_test() {
+        use args::ArgType;
+        let cmdr = Builder::default_config("adf").add_action_with_args(
+            "action",
+            "",
+            ArgSpec::new()
+                .optional("a", ArgType::Str)
+                .required("b", ArgType::Str),
+            |_, _| (),
+        );
+        assert_eq!(
+            cmdr,
+            Err(BuildError::InvalidArgSpec(
+                "required argument 'b' follows an optional argument".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn add_arg_completer_test() {
+        let cmdr = Builder::default_config("root")
+            .add_action("action", "", |_, _| ())
+            .begin_class("class", "")
+            .add_arg_completer("action", |_, _, _| Vec::new());
+        assert_eq!(cmdr, Err(BuildError::NameNotFound("action".to_string())));
+
+        let cmdr = Builder::default_config("root")
+            .add_action("action", "", |_, _| ())
+            .add_arg_completer("ACTION", |_, _, _| Vec::new())
+            .unwrap();
+        assert!(cmdr.current.actions[0].completer.is_some());
+
+        let cmdr = Builder::default_config("root")
+            .add_action("action", "", |_, _| ())
+            .add_alias("action", "act")
+            .add_arg_completer("act", |_, _, _| Vec::new())
+            .unwrap();
+        assert!(cmdr.current.actions[0].completer.is_some());
+    }
+
+    #[test]
+    fn add_alias_test() {
+        let cmdr = Builder::default_config("root")
+            .begin_class("list", "")
+            .end_class()
+            .add_action("remove", "", |_, _| ())
+            .add_alias("LIST", "LS")
+            .add_alias("remove", "rm")
+            .unwrap();
+        assert_eq!(cmdr.current.classes[0].aliases, vec!["ls"]);
+        assert_eq!(cmdr.current.actions[0].aliases, vec!["rm"]);
+
+        let cmdr = cmdr.add_alias("list", "rm");
         assert_eq!(cmdr, Err(BuildError::NameExistsAsAction));
+
+        let cmdr = Builder::default_config("root")
+            .begin_class("list", "")
+            .end_class()
+            .add_alias("list", "ls")
+            .add_action("ls", "", |_, _| ());
+        assert_eq!(cmdr, Err(BuildError::NameExistsAsClass));
+
+        let cmdr = Builder::default_config("root").add_alias("list", "ls");
+        assert_eq!(cmdr, Err(BuildError::NameNotFound("list".to_string())));
+
+        let cmdr = Builder::default_config("root")
+            .add_action("list", "", |_, _| ())
+            .add_alias("list", "exit");
+        assert_eq!(cmdr, Err(BuildError::NameIsBuiltin("exit".to_string())));
+    }
+
+    #[test]
+    fn with_builtins_test() {
+        let builtins = Builtins::default()
+            .set_names(Builtin::Cancel, &["cancel"])
+            .rename(Builtin::Exit, "quit");
+
+        let cmdr = Builder::default_config("root")
+            .with_builtins(builtins.clone())
+            .add_action("c", "", |_, _| ())
+            .begin_class("exit", "");
+        assert!(cmdr.is_ok());
+        let cmdr = cmdr.add_action("quit", "", |_, _| ());
+        assert_eq!(cmdr, Err(BuildError::NameIsBuiltin("quit".to_string())));
+
+        // names already added are checked, at any depth
+        let cmdr = Builder::default_config("root")
+            .begin_class("one", "")
+            .begin_class("two", "")
+            .begin_class("quit", "")
+            .end_class()
+            .end_class()
+            .begin_class("three", "")
+            .with_builtins(builtins.clone());
+        assert_eq!(cmdr, Err(BuildError::NameExistsAsClass));
+
+        let cmdr =
+            Builder::default_config("root").with_builtins(builtins.alias(Builtin::Help, "quit"));
+        assert_eq!(cmdr, Err(BuildError::DuplicateBuiltin("quit".to_string())));
+
+        let invalid = |names: &[&str]| {
+            Builder::default_config("root")
+                .with_builtins(Builtins::default().set_names(Builtin::Root, names))
+                .map(|_| ())
+        };
+        let err = |name: &str| Err(BuildError::InvalidBuiltinName(name.to_string()));
+        assert_eq!(invalid(&[""]), err(""));
+        assert_eq!(invalid(&["/", "to root"]), err("to root"));
+        assert_eq!(invalid(&["/", "root."]), err("root."));
+        assert_eq!(invalid(&["/", "."]), Ok(()));
     }
 
     #[test]
@@ -0,0 +1,290 @@
+//! Configuration of the builtin commands.
+//!
+//! The builtin commands are available from every class, and take precedence over class and action names.
+//! By default `help`, `cancel` (aliased `c`), `..`, `/` and `exit` are enabled. `alias` and `unalias` are
+//! available but disabled, see [`Commander::set_alias_builtin`](crate::Commander::set_alias_builtin),
+//! as are `back`, `forward`, `pushd` and `popd`, see
+//! [`Commander::set_history_builtins`](crate::Commander::set_history_builtins).
+//!
+//! A [`Builtins`] configuration is given to a `Builder` with
+//! [`with_builtins`](crate::builder::BuilderChain::with_builtins) to rename, alias, disable or add builtins.
+//!
+//! # Example
+//!
+//! ```rust
+//! use cmdtree::*;
+//! use cmdtree::builtins::{Builtin, Builtins};
+//!
+//! let mut cmder = Builder::default_config("base")
+//!     .with_builtins(
+//!         Builtins::default()
+//!             .rename(Builtin::Exit, "quit")
+//!             .alias(Builtin::Exit, "q")
+//!             .set_names(Builtin::Cancel, &["cancel"]),
+//!     )
+//!     .add_action("c", "compile", |_, _| ())
+//!     .into_commander().unwrap();
+//!
+//! assert_eq!(cmder.parse_line("c", false, &mut std::io::sink()), LineResult::Action(()));
+//! assert_eq!(cmder.parse_line("q", false, &mut std::io::sink()), LineResult::Exit);
+//! ```
+
+/// A builtin command.
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+pub enum Builtin {
+    /// Prints the help messages of the class.
+    Help,
+    /// Returns to the root class.
+    Cancel,
+    /// Moves up to the parent class.
+    Up,
+    /// Moves to the root class, and prefixes paths addressed from the root, such as `/one.two`.
+    Root,
+    /// Sends the exit signal.
+    Exit,
+    /// Lists, shows or defines runtime aliases.
+    Alias,
+    /// Removes a runtime alias.
+    Unalias,
+    /// Returns to the previous class in the navigation history.
+    Back,
+    /// Undoes a `Back`.
+    Forward,
+    /// Saves the current class on the class stack, then navigates.
+    Push,
+    /// Returns to the class on top of the class stack.
+    Pop,
+}
+
+impl Builtin {
+    /// All the builtins, in the order they are listed in help messages.
+    pub const ALL: [Builtin; 11] = [
+        Builtin::Help,
+        Builtin::Cancel,
+        Builtin::Up,
+        Builtin::Root,
+        Builtin::Exit,
+        Builtin::Alias,
+        Builtin::Unalias,
+        Builtin::Back,
+        Builtin::Forward,
+        Builtin::Push,
+        Builtin::Pop,
+    ];
+
+    /// The help message of the builtin.
+    pub fn help(self) -> &'static str {
+        match self {
+            Builtin::Help => "prints the help messages",
+            Builtin::Cancel => "returns to the root class",
+            Builtin::Up => "moves up to the parent class",
+            Builtin::Root => "moves to the root class, or prefixes a path from the root",
+            Builtin::Exit => "sends the exit signal to end the interactive loop",
+            Builtin::Alias => "lists, shows or defines aliases, define with <name> = <command...>",
+            Builtin::Unalias => "removes an alias",
+            Builtin::Back => "returns to the previous class",
+            Builtin::Forward => "returns to the class before the last back",
+            Builtin::Push => "saves the current class on the stack, then moves to the class given",
+            Builtin::Pop => "returns to the class on top of the stack",
+        }
+    }
+
+    /// The names of the builtin in the default configuration.
+    fn default_names(self) -> &'static [&'static str] {
+        match self {
+            Builtin::Help => &["help"],
+            Builtin::Cancel => &["cancel", "c"],
+            Builtin::Up => &[".."],
+            Builtin::Root => &["/"],
+            Builtin::Exit => &["exit"],
+            Builtin::Alias => &["alias"],
+            Builtin::Unalias => &["unalias"],
+            Builtin::Back => &["back"],
+            Builtin::Forward => &["forward"],
+            Builtin::Push => &["pushd"],
+            Builtin::Pop => &["popd"],
+        }
+    }
+}
+
+/// The names each builtin command is matched by.
+///
+/// A builtin with no names is disabled. The first name is the primary name, the rest are aliases.
+#[derive(Debug, PartialEq, Clone)]
+pub struct Builtins {
+    names: Vec<(Builtin, Vec<String>)>,
+}
+
+impl Default for Builtins {
+    /// `help`, `cancel | c`, `..`, `/` and `exit`, with the alias and history builtins disabled.
+    fn default() -> Self {
+        Builtins::none()
+            .enable(Builtin::Help)
+            .enable(Builtin::Cancel)
+            .enable(Builtin::Up)
+            .enable(Builtin::Root)
+            .enable(Builtin::Exit)
+    }
+}
+
+impl Builtins {
+    /// A configuration with every builtin disabled.
+    pub fn none() -> Self {
+        Builtins {
+            names: Builtin::ALL.iter().map(|&b| (b, Vec::new())).collect(),
+        }
+    }
+
+    /// Enable a builtin with its default names.
+    pub fn enable(self, builtin: Builtin) -> Self {
+        self.set_names(builtin, builtin.default_names())
+    }
+
+    /// Disable a builtin, removing all its names.
+    pub fn disable(self, builtin: Builtin) -> Self {
+        self.set_names(builtin, &[])
+    }
+
+    /// Replace the primary name of a builtin, keeping its aliases.
+    /// A disabled builtin is enabled with the name.
+    pub fn rename(mut self, builtin: Builtin, name: &str) -> Self {
+        let names = self.names_mut(builtin);
+        let name = name.to_lowercase();
+        if names.is_empty() {
+            names.push(name);
+        } else {
+            names[0] = name;
+        }
+        self
+    }
+
+    /// Add an alias of a builtin.
+    /// A disabled builtin is enabled with the alias as its name.
+    pub fn alias(mut self, builtin: Builtin, alias: &str) -> Self {
+        self.names_mut(builtin).push(alias.to_lowercase());
+        self
+    }
+
+    /// Set all the names of a builtin. An empty slice disables the builtin.
+    pub fn set_names(mut self, builtin: Builtin, names: &[&str]) -> Self {
+        *self.names_mut(builtin) = names.iter().map(|x| x.to_lowercase()).collect();
+        self
+    }
+
+    /// The names of a builtin, the primary name first.
+    pub fn names(&self, builtin: Builtin) -> &[String] {
+        self.names
+            .iter()
+            .find(|x| x.0 == builtin)
+            .map(|x| x.1.as_slice())
+            .unwrap_or(&[])
+    }
+
+    /// Whether the builtin has any names.
+    pub fn is_enabled(&self, builtin: Builtin) -> bool {
+        !self.names(builtin).is_empty()
+    }
+
+    /// The enabled builtins, with their names.
+    pub fn iter(&self) -> impl Iterator<Item = (Builtin, &[String])> {
+        self.names
+            .iter()
+            .filter(|x| !x.1.is_empty())
+            .map(|x| (x.0, x.1.as_slice()))
+    }
+
+    /// The builtin matched by a word, ignoring case.
+    pub fn find(&self, word: &str) -> Option<Builtin> {
+        let word = word.to_lowercase();
+        self.iter()
+            .find(|(_, names)| names.contains(&word))
+            .map(|(b, _)| b)
+    }
+
+    /// Returns the first name shared by two builtins, if any.
+    pub fn duplicate(&self) -> Option<&str> {
+        let mut seen: Vec<&str> = Vec::new();
+        for (_, names) in self.iter() {
+            for name in names {
+                if seen.contains(&name.as_str()) {
+                    return Some(name);
+                }
+                seen.push(name);
+            }
+        }
+        None
+    }
+
+    /// Returns the first name which cannot be entered as a single word, if any.
+    ///
+    /// Names cannot be empty or contain whitespace, and cannot contain a `.` unless they are only `.`s, such as `..`,
+    /// as a `.` separates the classes of a path.
+    pub fn invalid(&self) -> Option<&str> {
+        self.iter()
+            .flat_map(|(_, names)| names)
+            .map(String::as_str)
+            .find(|name| {
+                name.is_empty()
+                    || name.contains(char::is_whitespace)
+                    || (name.contains('.') && name.chars().any(|c| c != '.'))
+            })
+    }
+
+    fn names_mut(&mut self, builtin: Builtin) -> &mut Vec<String> {
+        &mut self
+            .names
+            .iter_mut()
+            .find(|x| x.0 == builtin)
+            .expect("every builtin has an entry")
+            .1
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builtins_test() {
+        let b = Builtins::default();
+        assert_eq!(b.find("HELP"), Some(Builtin::Help));
+        assert_eq!(b.find("c"), Some(Builtin::Cancel));
+        assert_eq!(b.find("alias"), None);
+        assert_eq!(b.find(".."), Some(Builtin::Up));
+        assert_eq!(b.find("/"), Some(Builtin::Root));
+        assert!(!b.is_enabled(Builtin::Unalias));
+
+        let b = b
+            .rename(Builtin::Exit, "Quit")
+            .alias(Builtin::Exit, "q")
+            .disable(Builtin::Help)
+            .rename(Builtin::Alias, "def")
+            .disable(Builtin::Up)
+            .set_names(Builtin::Root, &["~"]);
+        assert_eq!(b.names(Builtin::Exit), &["quit", "q"]);
+        assert_eq!(b.find("exit"), None);
+        assert_eq!(b.find("q"), Some(Builtin::Exit));
+        assert_eq!(b.find("help"), None);
+        assert_eq!(b.find("def"), Some(Builtin::Alias));
+        assert_eq!(
+            b.iter().map(|x| x.0).collect::<Vec<_>>(),
+            vec![
+                Builtin::Cancel,
+                Builtin::Root,
+                Builtin::Exit,
+                Builtin::Alias
+            ]
+        );
+        assert_eq!(b.duplicate(), None);
+
+        let b = b.alias(Builtin::Alias, "c");
+        assert_eq!(b.duplicate(), Some("c"));
+        assert_eq!(b.invalid(), None);
+
+        let invalid = |name| Builtins::default().alias(Builtin::Help, name);
+        assert_eq!(invalid("").invalid(), Some(""));
+        assert_eq!(invalid("a b").invalid(), Some("a b"));
+        assert_eq!(invalid("a.b").invalid(), Some("a.b"));
+        assert_eq!(invalid("...").invalid(), None);
+    }
+}
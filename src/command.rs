@@ -0,0 +1,389 @@
+//! Command trees described by Rust types.
+//!
+//! A type implementing [`CommandTree`] adds its commands to a `Builder`, and when one is invoked passes it,
+//! with its arguments already converted, to a dispatch closure. [`CommandTree::builder`] dispatches
+//! to a [`Handler`], so the actions are matched on a type rather than on `&[&str]`.
+//!
+//! With the `derive` feature, `#[derive(CommandTree)]` implements the trait:
+//!
+//! - An enum is a class, each variant a command of the class.
+//! - A unit variant is an action without arguments.
+//! - A variant with named fields is an action, the fields its arguments, see [`ArgField`].
+//! - A variant with a single unnamed field adds the field's `CommandTree` with the variant's name,
+//!   so a nested enum is a nested class, and a struct is an action.
+//! - A struct with named fields is an action, the fields its arguments.
+//! - Names are the kebab-case of the variant or struct name, the doc comments are the help messages.
+//!   `#[cmdtree(name = "...")]` renames a command and `#[cmdtree(alias = "...")]` adds an alias.
+//! - Arguments are named by the kebab-case of the field name, so `dry_run: bool` is the `--dry-run` flag.
+//!   `#[cmdtree(name = "...")]` renames a field.
+//!
+//! # Example
+//!
+//! ```rust
+//! # #[cfg(feature = "derive")] {
+//! use cmdtree::*;
+//! use cmdtree::command::{CommandTree, Handler};
+//! use std::io::Write;
+//!
+//! #[derive(CommandTree)]
+//! enum Cmd {
+//!     /// Counts down from a number.
+//!     Countdown { n: i64, step: Option<i64> },
+//!     /// Database commands.
+//!     Db(DbCmd),
+//! }
+//!
+//! #[derive(CommandTree)]
+//! enum DbCmd {
+//!     /// Connects to a database.
+//!     Connect { host: String, verbose: bool },
+//!     /// Disconnects.
+//!     Close,
+//! }
+//!
+//! struct App;
+//!
+//! impl Handler<Cmd> for App {
+//!     type Output = String;
+//!
+//!     fn handle(&mut self, cmd: Cmd, _writer: &mut dyn Write) -> String {
+//!         match cmd {
+//!             Cmd::Countdown { n, step } => format!("{} by {}", n, step.unwrap_or(1)),
+//!             Cmd::Db(DbCmd::Connect { host, verbose }) => format!("{} {}", host, verbose),
+//!             Cmd::Db(DbCmd::Close) => "closed".to_string(),
+//!         }
+//!     }
+//! }
+//!
+//! let mut cmder = Cmd::builder("app", App).into_commander().unwrap();
+//! let w = &mut std::io::sink();
+//!
+//! assert_eq!(cmder.parse_line("countdown 10", false, w), LineResult::Action("10 by 1".to_string()));
+//! assert_eq!(cmder.parse_line("db connect local --verbose", false, w), LineResult::Action("local true".to_string()));
+//! assert!(matches!(cmder.parse_line("countdown ten", false, w), LineResult::InvalidArgs(_)));
+//! # }
+//! ```
+use super::*;
+use args::{ArgSpec, ArgType, ArgValue, Args};
+use builder::BuilderResult;
+use std::convert::TryFrom;
+
+#[cfg(feature = "derive")]
+pub use cmdtree_derive::CommandTree;
+
+/// A type describing a command tree.
+///
+/// Usually implemented with `#[derive(CommandTree)]`, see the [module documentation](crate::command).
+pub trait CommandTree: Sized {
+    /// Add the commands to the current class of the builder.
+    /// When a command is invoked, it is passed to `dispatch` along with the writer.
+    fn add_commands<R, S, F>(builder: Builder<R, S>, dispatch: F) -> BuilderResult<R, S>
+    where
+        F: Fn(Self, &mut dyn Write) -> R + Clone + Send + 'static;
+
+    /// Add the commands as a child of the current class, named `name`.
+    /// An enum adds a class, a struct an action.
+    fn add_child<R, S, F>(
+        builder: Builder<R, S>,
+        name: &str,
+        help: &str,
+        dispatch: F,
+    ) -> BuilderResult<R, S>
+    where
+        F: Fn(Self, &mut dyn Write) -> R + Clone + Send + 'static;
+
+    /// A `Builder` with the commands added to the root class, dispatching them to `handler`.
+    fn builder<H>(root_name: &str, handler: H) -> BuilderResult<H::Output>
+    where
+        H: Handler<Self> + Send + 'static,
+    {
+        let handler = Arc::new(Mutex::new(handler));
+        Self::add_commands(Builder::new(root_name), move |cmd, writer| {
+            handler
+                .lock()
+                .expect("locking command handler failed")
+                .handle(cmd, writer)
+        })
+    }
+}
+
+/// Handles the commands of a [`CommandTree`].
+pub trait Handler<C> {
+    /// The data returned from handling a command, the `R` of the `Commander`.
+    type Output;
+
+    /// Handle an invoked command.
+    fn handle(&mut self, command: C, writer: &mut dyn Write) -> Self::Output;
+}
+
+/// A type which can be the field of a command, as an argument.
+///
+/// - `String`, the integer types and `f64` and `f32` are required arguments.
+///   An integer narrower than `i64` only accepts the values it can hold,
+///   and `u64` and `usize` values are limited to `i64::MAX`.
+/// - `Option<T>` is an optional argument.
+/// - `Vec<T>` is a variadic argument, so must be the last argument.
+/// - `bool` is a switch, `--name`.
+pub trait ArgField: Sized {
+    /// Add the argument `name` to the specification.
+    fn add_arg(spec: ArgSpec, name: &str) -> ArgSpec;
+
+    /// Read the argument `name` from arguments parsed with the specification.
+    fn from_args(args: &Args, name: &str) -> Self;
+}
+
+/// The types of argument values.
+trait ArgValueType: Sized {
+    const ARG_TYPE: ArgType;
+
+    fn from_value(value: &ArgValue) -> Option<Self>;
+}
+
+impl ArgValueType for String {
+    const ARG_TYPE: ArgType = ArgType::Str;
+
+    fn from_value(value: &ArgValue) -> Option<Self> {
+        value.as_str().map(String::from)
+    }
+}
+
+impl ArgValueType for i64 {
+    const ARG_TYPE: ArgType = ArgType::Int;
+
+    fn from_value(value: &ArgValue) -> Option<Self> {
+        value.as_int()
+    }
+}
+
+/// Integers narrower than `i64` are parsed as an `ArgType::IntRange` of the values they can hold.
+macro_rules! impl_int_value {
+    ($($t:ty),*) => {$(
+        impl ArgValueType for $t {
+            const ARG_TYPE: ArgType = ArgType::IntRange(
+                <$t>::MIN as i64,
+                if <$t>::MAX as u64 > i64::MAX as u64 { i64::MAX } else { <$t>::MAX as i64 },
+            );
+
+            fn from_value(value: &ArgValue) -> Option<Self> {
+                value.as_int().and_then(|i| <$t>::try_from(i).ok())
+            }
+        }
+    )*};
+}
+
+impl_int_value!(i8, i16, i32, isize, u8, u16, u32, u64, usize);
+
+impl ArgValueType for f64 {
+    const ARG_TYPE: ArgType = ArgType::Float;
+
+    fn from_value(value: &ArgValue) -> Option<Self> {
+        value.as_float()
+    }
+}
+
+impl ArgValueType for f32 {
+    const ARG_TYPE: ArgType = ArgType::Float32;
+
+    fn from_value(value: &ArgValue) -> Option<Self> {
+        value
+            .as_float()
+            .filter(|f| args::fits_f32(*f))
+            .map(|f| f as f32)
+    }
+}
+
+macro_rules! impl_arg_field {
+    ($($t:ty),*) => {$(
+        impl ArgField for $t {
+            fn add_arg(spec: ArgSpec, name: &str) -> ArgSpec {
+                spec.required(name, <$t>::ARG_TYPE)
+            }
+
+            fn from_args(args: &Args, name: &str) -> Self {
+                args.get(name)
+                    .and_then(<$t>::from_value)
+                    .expect("required arguments are validated")
+            }
+        }
+
+        impl ArgField for Option<$t> {
+            fn add_arg(spec: ArgSpec, name: &str) -> ArgSpec {
+                spec.optional(name, <$t>::ARG_TYPE)
+            }
+
+            fn from_args(args: &Args, name: &str) -> Self {
+                args.get(name).and_then(<$t>::from_value)
+            }
+        }
+
+        impl ArgField for Vec<$t> {
+            fn add_arg(spec: ArgSpec, name: &str) -> ArgSpec {
+                spec.variadic(name, <$t>::ARG_TYPE)
+            }
+
+            fn from_args(args: &Args, name: &str) -> Self {
+                args.get_all(name).iter().filter_map(<$t>::from_value).collect()
+            }
+        }
+    )*};
+}
+
+impl_arg_field!(String, i64, i8, i16, i32, isize, u8, u16, u32, u64, usize, f64, f32);
+
+impl ArgField for bool {
+    fn add_arg(spec: ArgSpec, name: &str) -> ArgSpec {
+        spec.flag(name, None)
+    }
+
+    fn from_args(args: &Args, name: &str) -> Self {
+        args.flag(name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A hand written implementation, as the derive would generate.
+    #[derive(Debug, PartialEq)]
+    enum Cmd {
+        Add {
+            a: i64,
+            b: Option<f64>,
+            rest: Vec<String>,
+            loud: bool,
+        },
+        Nested(Nested),
+    }
+
+    #[derive(Debug, PartialEq)]
+    enum Nested {
+        Stop,
+    }
+
+    impl CommandTree for Cmd {
+        fn add_commands<R, S, F>(builder: Builder<R, S>, dispatch: F) -> BuilderResult<R, S>
+        where
+            F: Fn(Self, &mut dyn Write) -> R + Clone + Send + 'static,
+        {
+            let d = dispatch.clone();
+            let spec = <i64 as ArgField>::add_arg(ArgSpec::new(), "a");
+            let spec = <Option<f64> as ArgField>::add_arg(spec, "b");
+            let spec = <Vec<String> as ArgField>::add_arg(spec, "rest");
+            let spec = <bool as ArgField>::add_arg(spec, "loud");
+            let builder = builder.add_action_with_args("add", "adds", spec, move |w, args| {
+                d(
+                    Cmd::Add {
+                        a: ArgField::from_args(args, "a"),
+                        b: ArgField::from_args(args, "b"),
+                        rest: ArgField::from_args(args, "rest"),
+                        loud: ArgField::from_args(args, "loud"),
+                    },
+                    w,
+                )
+            })?;
+            Nested::add_child(builder, "nested", "", move |c, w| {
+                dispatch(Cmd::Nested(c), w)
+            })
+        }
+
+        fn add_child<R, S, F>(
+            builder: Builder<R, S>,
+            name: &str,
+            help: &str,
+            dispatch: F,
+        ) -> BuilderResult<R, S>
+        where
+            F: Fn(Self, &mut dyn Write) -> R + Clone + Send + 'static,
+        {
+            Self::add_commands(builder.begin_class(name, help.to_string())?, dispatch)?.end_class()
+        }
+    }
+
+    impl CommandTree for Nested {
+        fn add_commands<R, S, F>(builder: Builder<R, S>, dispatch: F) -> BuilderResult<R, S>
+        where
+            F: Fn(Self, &mut dyn Write) -> R + Clone + Send + 'static,
+        {
+            builder.add_action_with_args("stop", "", ArgSpec::new(), move |w, _| {
+                dispatch(Nested::Stop, w)
+            })
+        }
+
+        fn add_child<R, S, F>(
+            builder: Builder<R, S>,
+            name: &str,
+            help: &str,
+            dispatch: F,
+        ) -> BuilderResult<R, S>
+        where
+            F: Fn(Self, &mut dyn Write) -> R + Clone + Send + 'static,
+        {
+            Self::add_commands(builder.begin_class(name, help.to_string())?, dispatch)?.end_class()
+        }
+    }
+
+    struct Recorder(Arc<Mutex<Vec<Cmd>>>);
+
+    impl Handler<Cmd> for Recorder {
+        type Output = ();
+
+        fn handle(&mut self, command: Cmd, _: &mut dyn Write) {
+            self.0.lock().unwrap().push(command);
+        }
+    }
+
+    #[test]
+    fn f32_value_test() {
+        assert_eq!(f32::from_value(&ArgValue::Float(0.5)), Some(0.5));
+        assert_eq!(
+            f32::from_value(&ArgValue::Float(f64::INFINITY)),
+            Some(f32::INFINITY)
+        );
+        assert_eq!(f32::from_value(&ArgValue::Float(1e300)), None);
+        assert_eq!(f32::from_value(&ArgValue::Float(-1e300)), None);
+    }
+
+    #[test]
+    fn handler_dispatch_test() {
+        let handled = Arc::new(Mutex::new(Vec::new()));
+        let mut cmder = Cmd::builder("base", Recorder(Arc::clone(&handled)))
+            .unwrap()
+            .into_commander()
+            .unwrap();
+        let w = &mut io::sink();
+
+        assert_eq!(
+            cmder.parse_line("add 1 --loud 2.5 x y", false, w),
+            LineResult::Action(())
+        );
+        assert_eq!(cmder.parse_line("add 3", false, w), LineResult::Action(()));
+        assert!(matches!(
+            cmder.parse_line("add one", false, w),
+            LineResult::InvalidArgs(_)
+        ));
+        assert_eq!(
+            cmder.parse_line("nested stop", false, w),
+            LineResult::Action(())
+        );
+
+        assert_eq!(
+            *handled.lock().unwrap(),
+            vec![
+                Cmd::Add {
+                    a: 1,
+                    b: Some(2.5),
+                    rest: vec!["x".to_string(), "y".to_string()],
+                    loud: true
+                },
+                Cmd::Add {
+                    a: 3,
+                    b: None,
+                    rest: Vec::new(),
+                    loud: false
+                },
+                Cmd::Nested(Nested::Stop),
+            ]
+        );
+    }
+}
@@ -1,18 +1,28 @@
 //! Completion of tree paths and action arguments.
 //!
 //! Completion is done functionally, see examples on github for how to implement.
+//!
+//! Completion scripts for shells can also be generated with [`Commander::write_shell_completion`],
+//! for when the tree backs a binary invoked from the shell.
 
 use super::*;
 #[cfg(feature = "runnable")]
 use colored::*;
 #[cfg(feature = "runnable")]
 pub use linefeed::{Completer, Completion, Interface, Prompter, ReadResult, Terminal};
+use parse::{split_completed_word, Resolved};
+use std::collections::BTreeMap;
 
-impl<'r, R> Commander<R> {
+impl<R, S> Commander<R, S> {
     /// Run the `Commander` interactively, with a completer constructed on every loop.
     /// Consumes the instance, and blocks the thread until the loop is exited.
     ///
+    /// Lines are parsed with [`try_parse_chain_with`], so commands can be chained with `;`, `&&` and `||`.
+    /// Any data returned from an action is treated as a success.
+    ///
     /// See examples for how to construct a completer.
+    ///
+    /// [`try_parse_chain_with`]: Commander::try_parse_chain_with
     #[cfg(feature = "runnable")]
     pub fn run_with_completion<
         C: 'static + Completer<linefeed::DefaultTerminal>,
@@ -33,15 +43,210 @@ impl<'r, R> Commander<R> {
             interface.set_completer(Arc::new(completer));
 
             if let Ok(ReadResult::Input(s)) = interface.read_line() {
-                if let LineResult::Exit = self.parse_line(&s, true, &mut std::io::stdout()) {
-                    exit = true
-                }
+                exit = self
+                    .try_parse_chain_with(&s, true, &mut std::io::stdout(), |_| true)
+                    .expect("failed writing output to writer")
+                    .iter()
+                    .any(|r| matches!(r, LineResult::Exit));
                 interface.add_history_unique(s);
             }
         }
     }
 }
 
+/// A shell to generate a completion script for.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Shell {
+    /// Bash, using `complete -F`. Help messages are not shown.
+    Bash,
+    /// Zsh, using `_describe` with help messages as descriptions.
+    Zsh,
+    /// Fish, using `complete` with help messages as descriptions.
+    Fish,
+}
+
+impl<R, S> Commander<R, S> {
+    /// Write a completion script for `shell`, completing classes and actions as subcommands of `bin_name`.
+    ///
+    /// The script is built from the tree from the root, including aliases, and is intended to pair
+    /// with [`run_args`](Commander::run_args). Only the first line of a help message is used as a description.
+    ///
+    /// # Example
+    /// ```rust
+    /// use cmdtree::*;
+    /// use cmdtree::completion::Shell;
+    ///
+    /// let cmder = Builder::default_config("mytool")
+    ///     .begin_class("net", "network commands")
+    ///     .add_action("status", "print the network status", |_wtr, _args| ())
+    ///     .into_commander().unwrap();
+    ///
+    /// let mut script = Vec::new();
+    /// cmder.write_shell_completion(Shell::Fish, "mytool", &mut script).unwrap();
+    /// let script = String::from_utf8(script).unwrap();
+    ///
+    /// assert!(script.contains("complete -c mytool -f -n '__mytool_at net' -a 'status' -d 'print the network status'"));
+    /// ```
+    pub fn write_shell_completion<W: Write>(
+        &self,
+        shell: Shell,
+        bin_name: &str,
+        writer: &mut W,
+    ) -> io::Result<()> {
+        let tree = shell_tree(&self.root);
+        let fn_name: String = bin_name
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect();
+
+        match shell {
+            Shell::Bash => write_bash(&tree, bin_name, &fn_name, writer),
+            Shell::Zsh => write_zsh(&tree, bin_name, &fn_name, writer),
+            Shell::Fish => write_fish(&tree, bin_name, &fn_name, writer),
+        }
+    }
+}
+
+/// Words that can follow a class path (empty for the root), with their first help line.
+type ShellTree = BTreeMap<Vec<String>, Vec<(String, String)>>;
+
+fn shell_tree<R, S>(root: &SubClass<R, S>) -> ShellTree {
+    let mut tree = ShellTree::new();
+    let mut stack = vec![(Vec::new(), root)];
+
+    while let Some((path, class)) = stack.pop() {
+        let help = |h: &CmdStr| h.lines().next().unwrap_or("").to_string();
+        let mut items = Vec::new();
+
+        for action in class.actions.iter() {
+            items.extend(action.names().map(|n| (n.to_string(), help(&action.help))));
+        }
+
+        for sub in class.classes.iter() {
+            for name in sub.names() {
+                items.push((name.to_string(), help(&sub.help)));
+                let mut path = path.clone();
+                path.push(name.to_string());
+                stack.push((path, &**sub));
+            }
+        }
+
+        tree.insert(path, items);
+    }
+
+    tree
+}
+
+/// Quotes a word for bash or zsh, within single quotes.
+fn sh_quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', "'\\''"))
+}
+
+fn write_bash<W: Write>(
+    tree: &ShellTree,
+    bin_name: &str,
+    fn_name: &str,
+    writer: &mut W,
+) -> io::Result<()> {
+    writeln!(writer, "_{}() {{", fn_name)?;
+    writeln!(writer, "    local cur=\"${{COMP_WORDS[COMP_CWORD]}}\"")?;
+    writeln!(
+        writer,
+        "    local path=\"${{COMP_WORDS[*]:1:COMP_CWORD-1}}\""
+    )?;
+    writeln!(writer, "    local -a words")?;
+    writeln!(writer, "    case \"$path\" in")?;
+    for (path, items) in tree {
+        let words: Vec<_> = items.iter().map(|(name, _)| sh_quote(name)).collect();
+        writeln!(writer, "        {})", sh_quote(&path.join(" ")))?;
+        writeln!(writer, "            words=({})", words.join(" "))?;
+        writeln!(writer, "            ;;")?;
+    }
+    writeln!(writer, "    esac")?;
+    // compgen -W expands the words, so they are matched here instead
+    writeln!(writer, "    COMPREPLY=()")?;
+    writeln!(writer, "    local word")?;
+    writeln!(writer, "    for word in \"${{words[@]}}\"; do")?;
+    writeln!(
+        writer,
+        "        [[ \"$word\" == \"$cur\"* ]] && COMPREPLY+=(\"$word\")"
+    )?;
+    writeln!(writer, "    done")?;
+    writeln!(writer, "}}")?;
+    writeln!(writer, "complete -F _{} {}", fn_name, bin_name)
+}
+
+fn write_zsh<W: Write>(
+    tree: &ShellTree,
+    bin_name: &str,
+    fn_name: &str,
+    writer: &mut W,
+) -> io::Result<()> {
+    writeln!(writer, "#compdef {}", bin_name)?;
+    writeln!(writer)?;
+    writeln!(writer, "_{}() {{", fn_name)?;
+    writeln!(writer, "    local -a items")?;
+    writeln!(writer, "    case \"${{words[2,CURRENT-1]}}\" in")?;
+    for (path, items) in tree {
+        writeln!(writer, "        {})", sh_quote(&path.join(" ")))?;
+        writeln!(writer, "            items=(")?;
+        for (name, help) in items {
+            let item = format!("{}:{}", name.replace(':', "\\:"), help);
+            writeln!(writer, "                {}", sh_quote(&item))?;
+        }
+        writeln!(writer, "            )")?;
+        writeln!(writer, "            ;;")?;
+    }
+    writeln!(writer, "    esac")?;
+    writeln!(writer, "    _describe 'command' items")?;
+    writeln!(writer, "}}")?;
+    writeln!(writer)?;
+    writeln!(writer, "_{} \"$@\"", fn_name)
+}
+
+fn write_fish<W: Write>(
+    tree: &ShellTree,
+    bin_name: &str,
+    fn_name: &str,
+    writer: &mut W,
+) -> io::Result<()> {
+    let quote = |s: &str| format!("'{}'", s.replace('\\', "\\\\").replace('\'', "\\'"));
+    // conditions and arguments are evaluated, so words within them are quoted unless plain
+    let word = |s: &str| {
+        if !s.is_empty() && s.chars().all(|c| c.is_alphanumeric() || "-_.".contains(c)) {
+            s.to_string()
+        } else {
+            quote(s)
+        }
+    };
+
+    writeln!(writer, "function __{}_at", fn_name)?;
+    writeln!(writer, "    set -l tokens (commandline -opc)")?;
+    writeln!(writer, "    test \"$tokens[2..-1]\" = \"$argv\"")?;
+    writeln!(writer, "end")?;
+    writeln!(writer)?;
+    writeln!(writer, "complete -c {} -f", bin_name)?;
+    for (path, items) in tree {
+        let mut condition = format!("__{}_at", fn_name);
+        for w in path {
+            condition.push(' ');
+            condition.push_str(&word(w));
+        }
+        for (name, help) in items {
+            writeln!(
+                writer,
+                "complete -c {} -f -n {} -a {} -d {}",
+                bin_name,
+                quote(&condition),
+                quote(&word(name)),
+                quote(help)
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
 /// Match string and qualified name of action.
 #[derive(Debug, PartialEq)]
 pub struct ActionMatch {
@@ -96,43 +301,55 @@ pub struct CompletionInfo {
 /// assert_eq!(v, vec!["two", "two three"]
 ///     .into_iter().map(|x| x.to_string()).collect::<Vec<_>>());
 /// ```
-pub fn create_tree_completion_items<R>(cmdr: &Commander<R>) -> Vec<CompletionInfo> {
-    cmdr.structure(false)
-        .into_iter()
-        .filter_map(|info| {
-            let StructureInfo {
-                path,
-                itemtype,
-                help_msg,
-            } = info;
-
-            let completestr =
-                path.split('.')
-                    .filter(|x| !x.is_empty())
-                    .fold(String::new(), |mut s, x| {
-                        if !s.is_empty() {
-                            s.push(' ');
-                        }
-                        s.push_str(x);
-                        s
-                    });
+pub fn create_tree_completion_items<R, S>(cmdr: &Commander<R, S>) -> Vec<CompletionInfo> {
+    class_completion_items(&cmdr.current)
+}
 
-            if completestr.is_empty() {
-                None
-            } else {
-                Some(CompletionInfo {
-                    completestr,
-                    itemtype,
-                    help_msg,
-                })
+/// The completion items below `class`, see [`create_tree_completion_items`].
+fn class_completion_items<R, S>(class: &SubClass<R, S>) -> Vec<CompletionInfo> {
+    // each item is keyed by its structure path, so the items are ordered as in `structure`.
+    // aliases are included as items of their own.
+    let mut items = Vec::new();
+    let mut stack = vec![(String::new(), String::new(), class)];
+
+    while let Some((path, prefix, class)) = stack.pop() {
+        for action in class.actions.iter() {
+            for name in action.names() {
+                let info = CompletionInfo {
+                    completestr: format!("{}{}", prefix, name),
+                    itemtype: ItemType::Action,
+                    help_msg: action.help.clone(),
+                };
+                items.push((format!("{}..{}", path, name), info));
             }
-        })
-        .collect()
+        }
+
+        for sub in class.classes.iter() {
+            for name in sub.names() {
+                let path = if path.is_empty() {
+                    name.to_string()
+                } else {
+                    format!("{}.{}", path, name)
+                };
+                let completestr = format!("{}{}", prefix, name);
+                let info = CompletionInfo {
+                    completestr: completestr.clone(),
+                    itemtype: ItemType::Class,
+                    help_msg: sub.help.clone(),
+                };
+                items.push((path.clone(), info));
+                stack.push((path, completestr + " ", &**sub));
+            }
+        }
+    }
+
+    items.sort_by(|a, b| a.0.cmp(&b.0));
+    items.into_iter().map(|x| x.1).collect()
 }
 
 /// Constructs a set of space delimited actions that could be completed at the
 /// current path.
-pub fn create_action_completion_items<R>(cmdr: &Commander<R>) -> Vec<ActionMatch> {
+pub fn create_action_completion_items<R, S>(cmdr: &Commander<R, S>) -> Vec<ActionMatch> {
     let cpath = cmdr.path();
     let rname = cmdr.root_name();
 
@@ -150,6 +367,7 @@ pub fn create_action_completion_items<R>(cmdr: &Commander<R>) -> Vec<ActionMatch
                 path,
                 itemtype,
                 help_msg,
+                ..
             } = x;
 
             let qualified_path = path.clone();
@@ -181,6 +399,122 @@ pub fn create_action_completion_items<R>(cmdr: &Commander<R>) -> Vec<ActionMatch
         .collect()
 }
 
+/// A ready made completer of classes, actions and the builtin commands, relative to the current class.
+///
+/// Arguments of actions with an argument completer, added with
+/// [`add_arg_completer`](builder::BuilderChain::add_arg_completer), are completed by that completer.
+/// The completers share the `Commander` state, reading it as it is when the completion is requested.
+///
+/// Implements linefeed's [`Completer`] with the `runnable` feature, and can be passed directly to
+/// [`run_with_completion`](Commander::run_with_completion).
+///
+/// # Example
+/// ```rust
+/// use cmdtree::*;
+/// use cmdtree::completion::TreeCompleter;
+///
+/// let cmder = Builder::default_config("eg")
+///     .begin_class("one", "")
+///     .add_action("two", "", |_, _| ())
+///     .into_commander().unwrap();
+///
+/// let completer = TreeCompleter::new(&cmder);
+/// assert_eq!(completer.completions("one "), vec!["two", "help", "cancel", "..", "/", "exit"]);
+/// assert_eq!(completer.completions("e"), vec!["exit"]);
+///
+/// // cmder.run_with_completion(TreeCompleter::new);
+/// ```
+pub struct TreeCompleter {
+    complete: Box<CompleteFn>,
+    builtins: Vec<String>,
+}
+
+/// Completes a word, given the words preceding it.
+type CompleteFn = dyn Fn(&[String], &str) -> Vec<String> + Send + Sync;
+
+impl TreeCompleter {
+    /// Construct a completer from the current class of the `Commander`.
+    pub fn new<R, S>(cmdr: &Commander<R, S>) -> Self
+    where
+        R: 'static,
+        S: Send + Sync + 'static,
+    {
+        let resolver = cmdr.resolver();
+        let state = Arc::clone(&cmdr.state);
+        let builtins: Vec<String> = cmdr
+            .builtins
+            .iter()
+            .map(|(_, names)| names[0].clone())
+            .collect();
+
+        let complete = {
+            let builtins = builtins.clone();
+            move |preceding: &[String], word: &str| match resolver.resolve(preceding) {
+                Resolved::Class(class) => class_completion_items(&class)
+                    .into_iter()
+                    .map(|x| x.completestr)
+                    .chain(builtins.iter().cloned())
+                    .filter(|x| x.starts_with(word))
+                    .collect(),
+                Resolved::Action(class, index, args) => match &class.actions[index].completer {
+                    Some(completer) => {
+                        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+                        let state = state.read().unwrap_or_else(PoisonError::into_inner);
+                        completer(&state, &args, word)
+                    }
+                    None => Vec::new(),
+                },
+                Resolved::Other => Vec::new(),
+            }
+        };
+
+        Self {
+            complete: Box::new(complete),
+            builtins,
+        }
+    }
+
+    /// The completions of the final word in `line`.
+    ///
+    /// The preceding words are resolved as `parse_line` would, so quoting, chained commands, runtime aliases,
+    /// abbreviations and dotted paths are followed.
+    /// Classes and actions at any depth below the class they resolve to are completed, as in [`tree_completions`],
+    /// along with the builtin commands.
+    /// If they resolve to an action with an argument completer, only its candidates are returned.
+    pub fn completions(&self, line: &str) -> Vec<String> {
+        match split_completed_word(line) {
+            Some((preceding, word)) => (self.complete)(&preceding, &word),
+            None => Vec::new(),
+        }
+    }
+}
+
+impl fmt::Debug for TreeCompleter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("TreeCompleter")
+            .field("builtins", &self.builtins)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(feature = "runnable")]
+impl<T: Terminal> Completer<T> for TreeCompleter {
+    fn complete(
+        &self,
+        _word: &str,
+        prompter: &Prompter<T>,
+        _start: usize,
+        end: usize,
+    ) -> Option<Vec<Completion>> {
+        Some(
+            self.completions(&prompter.buffer()[..end])
+                .into_iter()
+                .map(Completion::simple)
+                .collect(),
+        )
+    }
+}
+
 /// Determines from a set of items the ones that could be
 /// completed from the given line.
 ///
@@ -364,6 +698,289 @@ mod tests {
         assert_eq!(completions, vec!["inner-class1", "inner-class1 name",]);
     }
 
+    #[test]
+    fn tree_completer_test() {
+        let mut cmder = Builder::default_config("eg")
+            .begin_class("class1", "")
+            .begin_class("inner", "")
+            .add_action("name", "", |_, _| ())
+            .end_class()
+            .end_class()
+            .add_action("count", "", |_, _| ())
+            .into_commander()
+            .unwrap();
+
+        let completer = TreeCompleter::new(&cmder);
+        assert_eq!(
+            completer.completions(""),
+            vec![
+                "count",
+                "class1",
+                "class1 inner",
+                "class1 inner name",
+                "help",
+                "cancel",
+                "..",
+                "/",
+                "exit"
+            ]
+        );
+        assert_eq!(
+            completer.completions("c"),
+            vec![
+                "count",
+                "class1",
+                "class1 inner",
+                "class1 inner name",
+                "cancel"
+            ]
+        );
+        assert_eq!(
+            completer.completions("class1 inner "),
+            vec!["name", "help", "cancel", "..", "/", "exit"]
+        );
+        assert_eq!(
+            completer.completions("class1 inner name "),
+            Vec::<String>::new()
+        );
+        assert_eq!(completer.completions("count e"), Vec::<String>::new());
+
+        cmder.parse_line("class1", true, &mut std::io::sink());
+
+        let completer = TreeCompleter::new(&cmder);
+        assert_eq!(completer.completions("inner n"), vec!["name"]);
+        assert_eq!(completer.completions("h"), vec!["help"]);
+
+        cmder.set_alias_builtin(true).unwrap();
+        let completer = TreeCompleter::new(&cmder);
+        assert_eq!(completer.completions("a"), vec!["alias"]);
+    }
+
+    #[test]
+    fn tree_completer_arg_completer_test() {
+        let mut cmder = Builder::default_config("eg")
+            .begin_class("nested", "")
+            .add_action("path", "", |_, _| ())
+            .add_arg_completer("path", |_, args, word| {
+                vec![format!("{}:{}", args.join(","), word)]
+            })
+            .end_class()
+            .add_action("path", "", |_, _| ())
+            .into_commander()
+            .unwrap();
+
+        let completer = TreeCompleter::new(&cmder);
+        assert_eq!(completer.completions("nested path "), vec![":"]);
+        assert_eq!(completer.completions("nested path a  b c"), vec!["a,b:c"]);
+        assert_eq!(completer.completions("path "), Vec::<String>::new());
+        assert_eq!(completer.completions("nested pa"), vec!["path"]);
+
+        cmder.parse_line("nested", true, &mut std::io::sink());
+
+        let completer = TreeCompleter::new(&cmder);
+        assert_eq!(completer.completions("path x"), vec![":x"]);
+    }
+
+    #[test]
+    fn tree_completer_resolves_as_parse_line_test() {
+        let mut cmder = Builder::default_config("eg")
+            .begin_class("nested", "")
+            .add_action("path", "", |_, _| ())
+            .add_arg_completer("path", |_, args, word| {
+                vec![format!("{}:{}", args.join(","), word)]
+            })
+            .end_class()
+            .begin_class("other", "")
+            .add_action("list", "", |_, _| ())
+            .into_commander()
+            .unwrap();
+        cmder.set_abbreviations(true);
+        cmder.set_runtime_alias("np", "nested path").unwrap();
+        cmder.set_history_builtins(true).unwrap();
+
+        let completer = TreeCompleter::new(&cmder);
+        // words are split as parse_line splits them
+        assert_eq!(
+            completer.completions("nested  path  a   b c"),
+            vec!["a,b:c"]
+        );
+        assert_eq!(
+            completer.completions("nested path 'a b' \"c"),
+            vec!["a b:c"]
+        );
+        assert_eq!(completer.completions("nested path 'a b"), vec![":a b"]);
+        assert_eq!(completer.completions("other; nested path "), vec![":"]);
+        // aliases, abbreviations and paths resolve to the action
+        assert_eq!(completer.completions("np a "), vec!["a:"]);
+        assert_eq!(completer.completions("nest pa x"), vec![":x"]);
+        assert_eq!(completer.completions("nested.path x"), vec![":x"]);
+        assert_eq!(completer.completions("/nested.path x"), vec![":x"]);
+        assert_eq!(completer.completions("eg.nested path x"), vec![":x"]);
+        assert_eq!(completer.completions("other .. nested path x"), vec![":x"]);
+        assert_eq!(completer.completions("pushd nested path x"), vec![":x"]);
+        // and classes to their items
+        assert_eq!(completer.completions("oth l"), vec!["list"]);
+        assert_eq!(
+            completer.completions("nested / o"),
+            vec!["other", "other list"]
+        );
+        assert_eq!(completer.completions("nestd "), Vec::<String>::new());
+    }
+
+    #[test]
+    fn tree_completer_shared_state_test() {
+        // not Clone, the completer reads the state the Commander owns
+        struct Names(Vec<String>);
+
+        let mut cmder = Builder::with_state("eg", Names(Vec::new()))
+            .add_state_action("add", "", |names: &mut Names, _, args| {
+                names.0.extend(args.iter().map(|x| x.to_string()))
+            })
+            .add_action("show", "", |_, _| ())
+            .add_arg_completer("show", |names: &Names, _, word| {
+                names
+                    .0
+                    .iter()
+                    .filter(|x| x.starts_with(word))
+                    .cloned()
+                    .collect()
+            })
+            .into_commander()
+            .unwrap();
+
+        let completer = TreeCompleter::new(&cmder);
+        assert_eq!(completer.completions("show "), Vec::<String>::new());
+
+        cmder.parse_line("add one two", true, &mut std::io::sink());
+        assert_eq!(completer.completions("show t"), vec!["two"]);
+
+        assert!(cmder.into_state().is_none());
+    }
+
+    #[test]
+    fn alias_completion_test() {
+        let cmder = Builder::default_config("eg")
+            .begin_class("list", "")
+            .add_action("all", "", |_, _| ())
+            .add_arg_completer("all", |_, _, _| vec!["arg".to_string()])
+            .add_alias("all", "a")
+            .end_class()
+            .add_alias("list", "ls")
+            .into_commander()
+            .unwrap();
+
+        let v: Vec<_> = create_tree_completion_items(&cmder)
+            .into_iter()
+            .map(|x| x.completestr)
+            .collect();
+        assert_eq!(
+            v,
+            vec_str(vec!["list", "list a", "list all", "ls", "ls a", "ls all"])
+        );
+
+        let completer = TreeCompleter::new(&cmder);
+        assert_eq!(completer.completions("ls a"), vec!["a", "all"]);
+        assert_eq!(completer.completions("ls a "), vec!["arg"]);
+        assert_eq!(completer.completions("list all "), vec!["arg"]);
+    }
+
+    fn shell_cmder() -> Commander<()> {
+        Builder::default_config("my-tool")
+            .begin_class("net", "network commands\nmore help")
+            .add_action("status", "don't panic", |_, _| ())
+            .add_alias("status", "st")
+            .end_class()
+            .add_action("quit", "", |_, _| ())
+            .add_action("$(it's)", "", |_, _| ())
+            .into_commander()
+            .unwrap()
+    }
+
+    fn shell_script(shell: Shell) -> String {
+        let mut script = Vec::new();
+        shell_cmder()
+            .write_shell_completion(shell, "my-tool", &mut script)
+            .unwrap();
+        String::from_utf8(script).unwrap()
+    }
+
+    #[test]
+    fn write_shell_completion_bash_test() {
+        assert_eq!(
+            shell_script(Shell::Bash),
+            r#"_my_tool() {
+    local cur="${COMP_WORDS[COMP_CWORD]}"
+    local path="${COMP_WORDS[*]:1:COMP_CWORD-1}"
+    local -a words
+    case "$path" in
+        '')
+            words=('quit' '$(it'\''s)' 'net')
+            ;;
+        'net')
+            words=('status' 'st')
+            ;;
+    esac
+    COMPREPLY=()
+    local word
+    for word in "${words[@]}"; do
+        [[ "$word" == "$cur"* ]] && COMPREPLY+=("$word")
+    done
+}
+complete -F _my_tool my-tool
+"#
+        );
+    }
+
+    #[test]
+    fn write_shell_completion_zsh_test() {
+        assert_eq!(
+            shell_script(Shell::Zsh),
+            r#"#compdef my-tool
+
+_my_tool() {
+    local -a items
+    case "${words[2,CURRENT-1]}" in
+        '')
+            items=(
+                'quit:'
+                '$(it'\''s):'
+                'net:network commands'
+            )
+            ;;
+        'net')
+            items=(
+                'status:don'\''t panic'
+                'st:don'\''t panic'
+            )
+            ;;
+    esac
+    _describe 'command' items
+}
+
+_my_tool "$@"
+"#
+        );
+    }
+
+    #[test]
+    fn write_shell_completion_fish_test() {
+        assert_eq!(
+            shell_script(Shell::Fish),
+            r#"function __my_tool_at
+    set -l tokens (commandline -opc)
+    test "$tokens[2..-1]" = "$argv"
+end
+
+complete -c my-tool -f
+complete -c my-tool -f -n '__my_tool_at' -a 'quit' -d ''
+complete -c my-tool -f -n '__my_tool_at' -a '\'$(it\\\'s)\'' -d ''
+complete -c my-tool -f -n '__my_tool_at' -a 'net' -d 'network commands'
+complete -c my-tool -f -n '__my_tool_at net' -a 'status' -d 'don\'t panic'
+complete -c my-tool -f -n '__my_tool_at net' -a 'st' -d 'don\'t panic'
+"#
+        );
+    }
+
     fn vec_str(v: Vec<&str>) -> Vec<String> {
         v.into_iter().map(|x| x.to_string()).collect()
     }
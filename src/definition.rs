@@ -0,0 +1,515 @@
+//! Building a command tree from a declarative definition.
+//!
+//! A [`ClassDef`] describes the classes, actions, help messages and aliases of a tree, and
+//! [`Handlers`] binds the actions to closures. The definition can be written in Rust, or read from
+//! JSON with the `serde` feature, TOML with the `toml` feature, or YAML with the `yaml` feature.
+//!
+//! Each action is bound to the handler registered under its qualified path, the class names from the
+//! top of the definition separated by `.`, followed by `..` and the action name, such as `db.pool..resize`.
+//! An action at the top of the definition has the path `..name`.
+//! An action can name a different handler path with `handler`, so it can be moved without changing the code.
+//!
+//! [`add_definition`](Builder::add_definition) returns `BuildError::MissingHandler` for an action without a handler,
+//! and `BuildError::UnboundHandler` for a handler no action was bound to.
+//!
+//! # Example
+//!
+//! ```rust
+//! # #[cfg(feature = "serde")] {
+//! use cmdtree::*;
+//! use cmdtree::definition::{ClassDef, Handlers};
+//!
+//! let def = ClassDef::from_json(r#"{
+//!     "name": "base",
+//!     "classes": [
+//!         {
+//!             "name": "db",
+//!             "help": "database commands",
+//!             "actions": [
+//!                 { "name": "connect", "help": "connect to a database", "aliases": ["conn"] },
+//!                 { "name": "close", "handler": "disconnect" }
+//!             ]
+//!         }
+//!     ]
+//! }"#).unwrap();
+//!
+//! let handlers = Handlers::new()
+//!     .add("db..connect", |_wtr, args| format!("connected to {}", args.join(" ")))
+//!     .add("disconnect", |_wtr, _args| "closed".to_string());
+//!
+//! let mut cmder = Builder::from_definition(&def, handlers).unwrap().into_commander().unwrap();
+//!
+//! assert_eq!(
+//!     cmder.parse_line("db conn localhost", false, &mut std::io::sink()),
+//!     LineResult::Action("connected to localhost".to_string())
+//! );
+//! # }
+//! ```
+use super::*;
+use builder::BuilderResult;
+
+/// A class of a definition.
+#[derive(Debug, PartialEq, Clone, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Deserialize),
+    serde(default, deny_unknown_fields)
+)]
+pub struct ClassDef {
+    /// The class name. The root name when the class is the top of a definition.
+    pub name: String,
+    /// The help message.
+    pub help: String,
+    /// Other names the class is matched by. The top class of a definition cannot have aliases.
+    pub aliases: Vec<String>,
+    /// The nested classes.
+    pub classes: Vec<ClassDef>,
+    /// The actions of the class.
+    pub actions: Vec<ActionDef>,
+}
+
+/// An action of a definition.
+#[derive(Debug, PartialEq, Clone, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Deserialize),
+    serde(default, deny_unknown_fields)
+)]
+pub struct ActionDef {
+    /// The action name.
+    pub name: String,
+    /// The help message.
+    pub help: String,
+    /// Other names the action is matched by.
+    pub aliases: Vec<String>,
+    /// The path of the handler to bind, if not the qualified path of the action.
+    pub handler: Option<String>,
+}
+
+impl ClassDef {
+    /// Read a definition from JSON.
+    /// A malformed definition is a `BuildError::InvalidDefinition`.
+    #[cfg(feature = "serde")]
+    pub fn from_json(json: &str) -> Result<Self, BuildError> {
+        serde_json::from_str(json).map_err(|e| BuildError::InvalidDefinition(e.to_string()))
+    }
+
+    /// Read a definition from TOML, the top level table being the root class.
+    /// A malformed definition is a `BuildError::InvalidDefinition`.
+    #[cfg(feature = "toml")]
+    pub fn from_toml(toml: &str) -> Result<Self, BuildError> {
+        toml::from_str(toml).map_err(|e| BuildError::InvalidDefinition(e.to_string()))
+    }
+
+    /// Read a definition from YAML.
+    /// A malformed definition is a `BuildError::InvalidDefinition`.
+    #[cfg(feature = "yaml")]
+    pub fn from_yaml(yaml: &str) -> Result<Self, BuildError> {
+        serde_yaml_ng::from_str(yaml).map_err(|e| BuildError::InvalidDefinition(e.to_string()))
+    }
+
+    /// Read a definition from a file, with the format chosen by the extension:
+    /// `.json`, `.toml`, or `.yaml` and `.yml`. Each format requires its feature.
+    ///
+    /// A file which cannot be read, has an unsupported extension, or is malformed is a
+    /// `BuildError::InvalidDefinition`, with a message starting with the path.
+    #[cfg(feature = "serde")]
+    pub fn from_file<P: AsRef<std::path::Path>>(path: P) -> Result<Self, BuildError> {
+        let path = path.as_ref();
+        let invalid =
+            |msg: String| BuildError::InvalidDefinition(format!("{}: {}", path.display(), msg));
+        let contents = std::fs::read_to_string(path).map_err(|e| invalid(e.to_string()))?;
+
+        let def = match path.extension().and_then(|x| x.to_str()) {
+            Some("json") => ClassDef::from_json(&contents),
+            #[cfg(feature = "toml")]
+            Some("toml") => ClassDef::from_toml(&contents),
+            #[cfg(feature = "yaml")]
+            Some("yaml") | Some("yml") => ClassDef::from_yaml(&contents),
+            _ => return Err(invalid("unsupported definition format".to_string())),
+        };
+
+        def.map_err(|e| match e {
+            BuildError::InvalidDefinition(msg) => invalid(msg),
+            e => e,
+        })
+    }
+}
+
+type Binder<R, S> = Box<dyn FnOnce(Builder<R, S>, &str, String) -> BuilderResult<R, S>>;
+
+/// A registry of action closures, keyed by handler path.
+///
+/// The closures take the same forms as the `BuilderChain` actions.
+/// Each handler is bound to one action, registering a path again replaces the handler.
+pub struct Handlers<R, S = ()> {
+    binders: BTreeMap<String, Binder<R, S>>,
+}
+
+impl<R: 'static, S: 'static> Handlers<R, S> {
+    /// An empty registry.
+    pub fn new() -> Self {
+        Handlers {
+            binders: BTreeMap::new(),
+        }
+    }
+
+    /// Register a handler, bound as with [`add_action`](BuilderChain::add_action).
+    pub fn add<F>(self, path: &str, closure: F) -> Self
+    where
+        F: FnMut(&mut dyn Write, &[&str]) -> R + Send + 'static,
+    {
+        self.bind(path, move |b, name, help| b.add_action(name, help, closure))
+    }
+
+    /// Register a handler, bound as with [`add_state_action`](BuilderChain::add_state_action).
+    pub fn add_state<F>(self, path: &str, closure: F) -> Self
+    where
+        F: FnMut(&mut S, &mut dyn Write, &[&str]) -> R + Send + 'static,
+    {
+        self.bind(path, move |b, name, help| {
+            b.add_state_action(name, help, closure)
+        })
+    }
+
+    /// Register a handler, bound as with [`add_context_action`](BuilderChain::add_context_action).
+    pub fn add_context<F>(self, path: &str, closure: F) -> Self
+    where
+        F: FnMut(&mut ActionContext<S>) -> R + Send + 'static,
+    {
+        self.bind(path, move |b, name, help| {
+            b.add_context_action(name, help, closure)
+        })
+    }
+
+    /// Register a handler, bound as with [`add_fallible_action`](BuilderChain::add_fallible_action).
+    pub fn add_fallible<F, E>(self, path: &str, closure: F) -> Self
+    where
+        F: FnMut(&mut dyn Write, &[&str]) -> Result<R, E> + Send + 'static,
+        E: Into<Box<dyn std::error::Error + Send + Sync>>,
+    {
+        self.bind(path, move |b, name, help| {
+            b.add_fallible_action(name, help, closure)
+        })
+    }
+
+    /// Register a handler with an argument specification, bound as with
+    /// [`add_action_with_args`](BuilderChain::add_action_with_args).
+    pub fn add_with_args<F>(self, path: &str, args: args::ArgSpec, closure: F) -> Self
+    where
+        F: FnMut(&mut dyn Write, &args::Args) -> R + Send + 'static,
+    {
+        self.bind(path, move |b, name, help| {
+            b.add_action_with_args(name, help, args, closure)
+        })
+    }
+
+    /// Register a handler with an argument specification, bound as with
+    /// [`add_context_action_with_args`](BuilderChain::add_context_action_with_args).
+    pub fn add_context_with_args<F, E>(self, path: &str, args: args::ArgSpec, closure: F) -> Self
+    where
+        F: FnMut(&mut ActionContext<S>) -> Result<R, E> + Send + 'static,
+        E: Into<Box<dyn std::error::Error + Send + Sync>>,
+    {
+        self.bind(path, move |b, name, help| {
+            b.add_context_action_with_args(name, help, args, closure)
+        })
+    }
+
+    /// The registered handler paths.
+    pub fn paths(&self) -> impl Iterator<Item = &str> {
+        self.binders.keys().map(String::as_str)
+    }
+
+    fn bind<F>(mut self, path: &str, binder: F) -> Self
+    where
+        F: FnOnce(Builder<R, S>, &str, String) -> BuilderResult<R, S> + 'static,
+    {
+        self.binders.insert(path.to_lowercase(), Box::new(binder));
+        self
+    }
+}
+
+impl<R: 'static, S: 'static> Default for Handlers<R, S> {
+    fn default() -> Self {
+        Handlers::new()
+    }
+}
+
+impl<R> Builder<R> {
+    /// Initialise a `Builder` from a definition, named by the definition's top class.
+    /// See [`add_definition`](Builder::add_definition).
+    pub fn from_definition(def: &ClassDef, handlers: Handlers<R>) -> BuilderResult<R> {
+        Builder::new(&def.name).add_definition(def, handlers)
+    }
+}
+
+impl<R, S> Builder<R, S> {
+    /// Add the classes and actions of a definition to the current class, binding each action to its handler.
+    ///
+    /// The name and help of the top class of the definition are not used.
+    /// Returns `BuildError::InvalidDefinition` if the top class has aliases,
+    /// `BuildError::MissingHandler` if an action has no handler,
+    /// and `BuildError::UnboundHandler` if a handler is not bound to any action.
+    pub fn add_definition(
+        self,
+        def: &ClassDef,
+        mut handlers: Handlers<R, S>,
+    ) -> BuilderResult<R, S> {
+        if !def.aliases.is_empty() {
+            return Err(BuildError::InvalidDefinition(format!(
+                "the top class '{}' cannot have aliases",
+                def.name
+            )));
+        }
+        let builder = add_class_def(self, def, "", &mut handlers)?;
+        match handlers.binders.keys().next() {
+            Some(path) => Err(BuildError::UnboundHandler(path.clone())),
+            None => Ok(builder),
+        }
+    }
+}
+
+fn add_class_def<R, S>(
+    mut builder: Builder<R, S>,
+    class: &ClassDef,
+    path: &str,
+    handlers: &mut Handlers<R, S>,
+) -> BuilderResult<R, S> {
+    for action in class.actions.iter() {
+        let name = action.name.to_lowercase();
+        let handler = match &action.handler {
+            Some(handler) => handler.to_lowercase(),
+            None => format!("{}..{}", path, name),
+        };
+        let bind = handlers
+            .binders
+            .remove(&handler)
+            .ok_or(BuildError::MissingHandler(handler))?;
+        builder = bind(builder, &name, action.help.clone())?;
+        for alias in action.aliases.iter() {
+            builder = builder.add_alias(&name, alias)?;
+        }
+    }
+
+    for child in class.classes.iter() {
+        let name = child.name.to_lowercase();
+        let child_path = if path.is_empty() {
+            name.clone()
+        } else {
+            format!("{}.{}", path, name)
+        };
+        builder = add_class_def(
+            builder.begin_class(&name, child.help.clone())?,
+            child,
+            &child_path,
+            handlers,
+        )?
+        .end_class()?;
+        for alias in child.aliases.iter() {
+            builder = builder.add_alias(&name, alias)?;
+        }
+    }
+
+    Ok(builder)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn def() -> ClassDef {
+        ClassDef {
+            name: "base".to_string(),
+            classes: vec![ClassDef {
+                name: "One".to_string(),
+                help: "one help".to_string(),
+                aliases: vec!["1".to_string()],
+                classes: vec![ClassDef {
+                    name: "two".to_string(),
+                    actions: vec![ActionDef {
+                        name: "deep".to_string(),
+                        ..ActionDef::default()
+                    }],
+                    ..ClassDef::default()
+                }],
+                actions: vec![ActionDef {
+                    name: "moved".to_string(),
+                    handler: Some("Elsewhere".to_string()),
+                    ..ActionDef::default()
+                }],
+            }],
+            actions: vec![ActionDef {
+                name: "top".to_string(),
+                help: "top help".to_string(),
+                aliases: vec!["t".to_string()],
+                handler: None,
+            }],
+            ..ClassDef::default()
+        }
+    }
+
+    fn handlers() -> Handlers<&'static str> {
+        Handlers::new()
+            .add("..top", |_, _| "top")
+            .add("one.two..deep", |_, _| "deep")
+            .add("elsewhere", |_, _| "moved")
+    }
+
+    #[test]
+    fn add_definition_test() {
+        let mut cmder = Builder::from_definition(&def(), handlers())
+            .unwrap()
+            .into_commander()
+            .unwrap();
+        let w = &mut io::sink();
+
+        assert_eq!(cmder.root_name(), "base");
+        assert_eq!(cmder.parse_line("t", false, w), LineResult::Action("top"));
+        assert_eq!(
+            cmder.parse_line("1 two deep", false, w),
+            LineResult::Action("deep")
+        );
+        assert_eq!(
+            cmder.parse_line("one moved", false, w),
+            LineResult::Action("moved")
+        );
+
+        let tree = cmder.tree(true);
+        assert_eq!(tree.actions[0].help, "top help");
+        assert_eq!(tree.classes[0].help, "one help");
+    }
+
+    #[test]
+    fn handler_errors_test() {
+        let missing = handlers().add("..top", |_, _| "replaced");
+        assert_eq!(missing.paths().count(), 3);
+        let mut def = def();
+        def.actions.push(ActionDef {
+            name: "new".to_string(),
+            ..ActionDef::default()
+        });
+        assert_eq!(
+            Builder::from_definition(&def, missing),
+            Err(BuildError::MissingHandler("..new".to_string()))
+        );
+
+        let unbound = handlers().add("one..gone", |_, _| "gone");
+        assert_eq!(
+            Builder::from_definition(&self::def(), unbound),
+            Err(BuildError::UnboundHandler("one..gone".to_string()))
+        );
+    }
+
+    #[test]
+    fn top_class_aliases_test() {
+        let mut def = def();
+        def.aliases.push("b".to_string());
+        assert_eq!(
+            Builder::from_definition(&def, handlers()),
+            Err(BuildError::InvalidDefinition(
+                "the top class 'base' cannot have aliases".to_string()
+            ))
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn from_json_test() {
+        let json = r#"{
+            "name": "base",
+            "actions": [ { "name": "top", "help": "top help", "aliases": ["t"] } ],
+            "classes": [
+                {
+                    "name": "One",
+                    "help": "one help",
+                    "aliases": ["1"],
+                    "classes": [ { "name": "two", "actions": [ { "name": "deep" } ] } ],
+                    "actions": [ { "name": "moved", "handler": "Elsewhere" } ]
+                }
+            ]
+        }"#;
+        assert_eq!(ClassDef::from_json(json), Ok(def()));
+
+        match ClassDef::from_json(r#"{ "name": "base", "action": [] }"#) {
+            Err(BuildError::InvalidDefinition(msg)) => assert!(msg.contains("unknown field")),
+            x => panic!("expected an invalid definition, got {:?}", x),
+        }
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn from_toml_test() {
+        let toml = r#"
+            name = "base"
+
+            [[actions]]
+            name = "top"
+            help = "top help"
+            aliases = ["t"]
+
+            [[classes]]
+            name = "One"
+            help = "one help"
+            aliases = ["1"]
+
+            [[classes.classes]]
+            name = "two"
+            actions = [ { name = "deep" } ]
+
+            [[classes.actions]]
+            name = "moved"
+            handler = "Elsewhere"
+        "#;
+        assert_eq!(ClassDef::from_toml(toml), Ok(def()));
+    }
+
+    #[cfg(feature = "yaml")]
+    #[test]
+    fn from_yaml_test() {
+        let yaml = "
+name: base
+actions:
+  - name: top
+    help: top help
+    aliases: [t]
+classes:
+  - name: One
+    help: one help
+    aliases: ['1']
+    classes:
+      - name: two
+        actions:
+          - name: deep
+    actions:
+      - name: moved
+        handler: Elsewhere
+";
+        assert_eq!(ClassDef::from_yaml(yaml), Ok(def()));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn from_file_test() {
+        match ClassDef::from_file("this-file-does-not-exist.json") {
+            Err(BuildError::InvalidDefinition(msg)) => {
+                assert!(msg.starts_with("this-file-does-not-exist.json: "))
+            }
+            x => panic!("expected an invalid definition, got {:?}", x),
+        }
+
+        let path = std::env::temp_dir().join(format!(
+            "cmdtree-from-file-test-{}.json",
+            std::process::id()
+        ));
+        std::fs::write(&path, "{ \"name\": ").unwrap();
+        let r = ClassDef::from_file(&path);
+        std::fs::remove_file(&path).unwrap();
+        match r {
+            Err(BuildError::InvalidDefinition(msg)) => {
+                assert!(msg.starts_with(&format!("{}: ", path.display())))
+            }
+            x => panic!("expected an invalid definition, got {:?}", x),
+        }
+    }
+}
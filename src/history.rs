@@ -0,0 +1,320 @@
+//! Navigation history, with a back and forward history and a stack of saved classes.
+use super::*;
+
+impl<R, S> Commander<R, S> {
+    /// Set whether the `back`, `forward`, `pushd` and `popd` builtins are available, with their default names.
+    ///
+    /// Off by default, they can also be configured with [`with_builtins`](builder::BuilderChain::with_builtins). When set, the following commands can be entered as the first word of a line:
+    ///
+    /// - `back` returns to the class before the last navigation.
+    /// - `forward` undoes a `back`.
+    /// - `pushd <path...>` saves the current class on the stack, then moves along the path.
+    ///   `pushd` without a path exchanges the current class with the class on top of the stack.
+    /// - `popd` returns to the class on top of the stack.
+    ///
+    /// Enabling the builtins fails if their names are taken, as with [`set_alias_builtin`](Commander::set_alias_builtin).
+    ///
+    /// Words following `back`, `forward` or `popd` are parsed from the class returned to.
+    /// The history is kept regardless of this setting, and the back history and the stack keep the 100 most recent classes. A line only enters the back history if it
+    /// ends at a different class, lines which invoke an action or print help return to where they started.
+    ///
+    /// # Example
+    /// ```rust
+    /// # use cmdtree::*;
+    /// let mut cmder = Builder::<()>::new("base")
+    ///     .begin_class("one", "")
+    ///     .begin_class("two", "")
+    ///     .end_class()
+    ///     .end_class()
+    ///     .begin_class("three", "")
+    ///     .into_commander().unwrap();
+    ///
+    /// cmder.set_history_builtins(true).unwrap();
+    /// let w = &mut std::io::sink();
+    /// cmder.parse_line("one two", false, w);
+    /// cmder.parse_line("pushd /three", false, w);
+    /// assert_eq!(cmder.path(), "base.three");
+    /// cmder.parse_line("back", false, w);
+    /// assert_eq!(cmder.path(), "base.one.two");
+    /// cmder.parse_line("forward", false, w);
+    /// assert_eq!(cmder.path(), "base.three");
+    /// cmder.parse_line("popd", false, w);
+    /// assert_eq!(cmder.path(), "base.one.two");
+    /// ```
+    pub fn set_history_builtins(&mut self, enable: bool) -> Result<(), BuildError> {
+        let history = [Builtin::Back, Builtin::Forward, Builtin::Push, Builtin::Pop];
+        let builtins = history.iter().fold(self.builtins.clone(), |b, &x| {
+            if enable {
+                b.enable(x)
+            } else {
+                b.disable(x)
+            }
+        });
+        self.set_builtins(builtins)
+    }
+
+    /// The paths of the back history, the most recent first.
+    pub fn back_history(&self) -> impl Iterator<Item = &str> {
+        self.back_history.iter().rev().map(|x| x.path.as_str())
+    }
+
+    /// The paths of the forward history, the next `forward` first.
+    pub fn forward_history(&self) -> impl Iterator<Item = &str> {
+        self.forward_history.iter().rev().map(|x| x.path.as_str())
+    }
+
+    /// The paths of the class stack, the top first.
+    pub fn dir_stack(&self) -> impl Iterator<Item = &str> {
+        self.dir_stack.iter().rev().map(|x| x.path.as_str())
+    }
+
+    /// Return to the previous class in the back history, returning `false` if the history is empty.
+    pub fn back(&mut self) -> bool {
+        match self.back_history.pop_back() {
+            Some(position) => {
+                let current = self.position();
+                self.forward_history.push(current);
+                self.set_position(position);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Undo a [`back`](Commander::back), returning `false` if the forward history is empty.
+    pub fn forward(&mut self) -> bool {
+        match self.forward_history.pop() {
+            Some(position) => {
+                let current = self.position();
+                self.back_history.push_back(current);
+                self.set_position(position);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Save the current class on top of the class stack.
+    pub fn push_dir(&mut self) {
+        let current = self.position();
+        self.push_dir_stack(current);
+    }
+
+    /// Return to the class on top of the class stack, removing it.
+    /// Returns `false` if the stack is empty.
+    pub fn pop_dir(&mut self) -> bool {
+        match self.dir_stack.pop_back() {
+            Some(position) => {
+                let start = self.position();
+                self.set_position(position);
+                self.record_history(start);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Exchange the current class with the class on top of the class stack, as `pushd` without a path does.
+    /// Returns `false` if the stack is empty.
+    pub fn swap_dir(&mut self) -> bool {
+        match self.dir_stack.pop_back() {
+            Some(position) => {
+                let start = self.position();
+                self.dir_stack.push_back(start.clone());
+                self.set_position(position);
+                self.record_history(start);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cmder() -> Commander<()> {
+        let mut cmder = Builder::new("base")
+            .begin_class("one", "")
+            .begin_class("two", "")
+            .add_action("act", "", |_, _| ())
+            .end_class()
+            .end_class()
+            .begin_class("three", "")
+            .into_commander()
+            .unwrap();
+        cmder.set_history_builtins(true).unwrap();
+        cmder
+    }
+
+    #[test]
+    fn back_forward_test() {
+        let mut cmder = cmder();
+        let w = &mut io::sink();
+
+        assert_eq!(cmder.parse_line("back", false, w), LineResult::NoHistory);
+        cmder.parse_line("one", false, w);
+        cmder.parse_line("two act", false, w); // actions do not navigate
+        cmder.parse_line("help", false, w);
+        cmder.parse_line("two", false, w);
+        cmder.parse_line("two", false, w); // unrecognized
+        cmder.parse_line("c", false, w);
+        assert_eq!(
+            cmder.back_history().collect::<Vec<_>>(),
+            vec!["base.one.two", "base.one", "base"]
+        );
+
+        assert_eq!(cmder.parse_line("back", false, w), LineResult::Class);
+        assert_eq!(cmder.path(), "base.one.two");
+        assert_eq!(cmder.parse_line("back", false, w), LineResult::Class);
+        assert_eq!(cmder.path(), "base.one");
+        assert_eq!(
+            cmder.forward_history().collect::<Vec<_>>(),
+            vec!["base.one.two", "base"]
+        );
+
+        assert!(cmder.forward());
+        assert_eq!(cmder.path(), "base.one.two");
+
+        // following words navigate from the class returned to
+        assert_eq!(cmder.parse_line("back ..", false, w), LineResult::Class);
+        assert_eq!(cmder.path(), "base");
+        assert_eq!(cmder.forward_history().count(), 0);
+        assert_eq!(cmder.parse_line("forward", false, w), LineResult::NoHistory);
+    }
+
+    #[test]
+    fn dir_stack_test() {
+        let mut cmder = cmder();
+        let w = &mut io::sink();
+
+        assert_eq!(cmder.parse_line("popd", false, w), LineResult::NoHistory);
+        assert_eq!(cmder.parse_line("pushd", false, w), LineResult::NoHistory);
+        assert_eq!(cmder.parse_line("pushd one", false, w), LineResult::Class);
+        // without a path the current class and the top of the stack are exchanged
+        assert_eq!(cmder.parse_line("pushd", false, w), LineResult::Class);
+        assert_eq!(cmder.path(), "base");
+        assert_eq!(cmder.dir_stack().collect::<Vec<_>>(), vec!["base.one"]);
+        assert_eq!(cmder.parse_line("pushd", false, w), LineResult::Class);
+        assert_eq!(cmder.path(), "base.one");
+        assert_eq!(cmder.dir_stack().collect::<Vec<_>>(), vec!["base"]);
+        assert_eq!(
+            cmder.parse_line("pushd two act", false, w),
+            LineResult::Action(())
+        );
+        assert_eq!(
+            cmder.parse_line("pushd four", false, w),
+            LineResult::Unrecognized {
+                word: "four".to_string(),
+                suggestions: Vec::new()
+            }
+        );
+        assert_eq!(cmder.parse_line("pushd two", false, w), LineResult::Class);
+        assert_eq!(
+            cmder.parse_line("pushd /three", false, w),
+            LineResult::Class
+        );
+        assert_eq!(
+            cmder.dir_stack().collect::<Vec<_>>(),
+            vec!["base.one.two", "base.one", "base"]
+        );
+
+        assert_eq!(cmder.parse_line("popd", false, w), LineResult::Class);
+        assert_eq!(cmder.path(), "base.one.two");
+        assert_eq!(cmder.parse_line("popd ..", false, w), LineResult::Class);
+        assert_eq!(cmder.path(), "base");
+        cmder.parse_line("popd", false, w);
+        assert_eq!(cmder.path(), "base");
+        assert_eq!(
+            cmder.back_history().collect::<Vec<_>>(),
+            vec![
+                "base.one",
+                "base.one.two",
+                "base.three",
+                "base.one.two",
+                "base.one",
+                "base",
+                "base.one",
+                "base"
+            ]
+        );
+    }
+
+    #[test]
+    fn history_limit_test() {
+        let mut cmder = cmder();
+        let w = &mut io::sink();
+
+        for _ in 0..HISTORY_LIMIT {
+            cmder.parse_line("one", false, w);
+            cmder.parse_line("..", false, w);
+        }
+        assert_eq!(cmder.back_history().count(), HISTORY_LIMIT);
+        assert_eq!(cmder.back_history().last(), Some("base"));
+        assert_eq!(cmder.back_history().next(), Some("base.one"));
+
+        for _ in 0..=HISTORY_LIMIT {
+            cmder.push_dir();
+        }
+        cmder.parse_line("one", false, w);
+        cmder.push_dir();
+        assert_eq!(cmder.dir_stack().count(), HISTORY_LIMIT);
+        assert_eq!(cmder.dir_stack().next(), Some("base.one"));
+        assert_eq!(cmder.dir_stack().last(), Some("base"));
+    }
+
+    #[test]
+    fn run_args_history_test() {
+        let mut cmder = cmder();
+        let w = &mut io::sink();
+
+        cmder.parse_line("one", false, w);
+        cmder.parse_line("back", false, w);
+        cmder
+            .run_args_with(vec!["base", "three"], false, w)
+            .unwrap();
+        cmder
+            .run_args_with(vec!["base", "pushd", "one"], false, w)
+            .unwrap();
+
+        assert_eq!(cmder.path(), "base");
+        assert_eq!(cmder.back_history().count(), 0);
+        assert_eq!(
+            cmder.forward_history().collect::<Vec<_>>(),
+            vec!["base.one"]
+        );
+        assert_eq!(cmder.dir_stack().count(), 0);
+    }
+
+    #[test]
+    fn history_builtin_names_test() {
+        let mut cmder = Builder::default_config("base")
+            .begin_class("one", "")
+            .begin_class("back", "")
+            .end_class()
+            .add_action("alias", "", |_, _| ())
+            .into_commander()
+            .unwrap();
+
+        assert_eq!(
+            cmder.set_history_builtins(true),
+            Err(BuildError::NameExistsAsClass)
+        );
+        assert_eq!(
+            cmder.set_alias_builtin(true),
+            Err(BuildError::NameExistsAsAction)
+        );
+        assert_eq!(cmder.builtins, Builtins::default());
+        assert_eq!(cmder.set_history_builtins(false), Ok(()));
+
+        let mut cmder = Builder::default_config("base")
+            .with_builtins(Builtins::default().rename(Builtin::Exit, "popd"))
+            .into_commander()
+            .unwrap();
+        assert_eq!(
+            cmder.set_history_builtins(true),
+            Err(BuildError::DuplicateBuiltin("popd".to_string()))
+        );
+    }
+}
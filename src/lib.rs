@@ -17,6 +17,7 @@
 //!
 //! ```rust,no_run
 //! extern crate cmdtree;
+//! use cmdtree::args::{ArgSpec, ArgType};
 //! use cmdtree::*;
 //!
 //! fn main() {
@@ -32,20 +33,16 @@
 //!     .add_action("echo", "repeat stuff", |mut wtr, args| {
 //!       writeln!(wtr, "{}", args.join(" ")).unwrap()
 //!     })
-//!     .add_action("countdown", "countdown from a number", |mut wtr, args| {
-//!       if args.len() != 1 {
-//!         println!("need one number",);
-//!       } else {
-//!         match str::parse::<u32>(args[0]) {
-//!           Ok(n) => {
-//!             for i in (0..=n).rev() {
-//!               writeln!(wtr, "{}", i).unwrap();
-//!             }
-//!           }
-//!           Err(_) => writeln!(wtr, "expecting a number!",).unwrap(),
+//!     .add_action_with_args(
+//!       "countdown",
+//!       "countdown from a number",
+//!       ArgSpec::new().required("n", ArgType::Int), // typed arguments
+//!       |mut wtr, args| {
+//!         for i in (0..=args.get_int("n").unwrap()).rev() {
+//!           writeln!(wtr, "{}", i).unwrap();
 //!         }
-//!       }
-//!     })
+//!       },
+//!     )
 //!     .into_commander() // can short-circuit the closing out of classes
 //!     .unwrap();
 //!
@@ -59,6 +56,8 @@
 //! cmdtree-example=> help            <-- Will print help messages
 //! help -- prints the help messages
 //! cancel | c -- returns to the root class
+//! .. -- moves up to the parent class
+//! / -- moves to the root class, or prefixes a path from the root
 //! exit -- sends the exit signal to end the interactive loop
 //! Classes:
 //!         class1 -- class1 help message
@@ -67,14 +66,17 @@
 //! cmdtree-example.print=> help
 //! help -- prints the help messages
 //! cancel | c -- returns to the root class
+//! .. -- moves up to the parent class
+//! / -- moves to the root class, or prefixes a path from the root
 //! exit -- sends the exit signal to end the interactive loop
 //! Actions:
 //!         echo -- repeat stuff
-//!         countdown -- countdown from a number
+//!         countdown <n> -- countdown from a number
 //! cmdtree-example.print=> echo hello, world!  <-- Call the actions
 //! hello, world!
 //! cmdtree-example.print=> countdown
-//! need one number
+//! missing argument 'n'
+//! usage: countdown <n>
 //! cmdtree-example.print=> countdown 10
 //! 10
 //! 9
@@ -87,25 +89,38 @@
 //! 2
 //! 1
 //! 0
-//! cmdtree-example.print=> exit      <-- exit the loop!
+//! cmdtree-example.print=> ..        <-- Go up a class
+//! cmdtree-example=> /print.echo hi  <-- Or address a path from the root
+//! hi
+//! cmdtree-example=> exit      <-- exit the loop!
 //! ```
 
 #![warn(missing_docs)]
 
 use std::borrow::Cow;
 use std::cmp::Ordering;
-use std::collections::BTreeSet;
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
 use std::fmt;
-use std::io::Write;
+use std::io::{self, BufRead, Write};
 use std::ops::Deref;
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Mutex, PoisonError, RwLock, RwLockReadGuard, RwLockWriteGuard};
 
+mod alias;
+pub mod args;
 pub mod builder;
+pub mod builtins;
+pub mod command;
 pub mod completion;
+pub mod definition;
+mod history;
+mod macros;
 mod parse;
+pub mod script;
+pub mod tree;
 
-pub use self::parse::LineResult;
+pub use self::parse::{ActionError, ActionOutcome, LineResult};
 pub use builder::{BuildError, Builder, BuilderChain};
+use builtins::{Builtin, Builtins};
 
 /// A constructed command tree.
 ///
@@ -113,13 +128,51 @@ pub use builder::{BuildError, Builder, BuilderChain};
 /// Alternatively, `parse_line` can be used to simulate a read input and update the command tree position.
 ///
 /// To construct a command tree, look at the [`builder` module](./builder/index.html).
-pub struct Commander<R> {
-    root: Arc<SubClass<R>>,
-    current: Arc<SubClass<R>>,
+///
+/// The `Commander` owns a user state `S`, which is passed to actions added with
+/// [`add_state_action`](builder::BuilderChain::add_state_action).
+/// The state defaults to `()`, use [`Builder::with_state`] to supply one.
+/// It is kept behind a lock which is shared with the argument completers of a
+/// [`TreeCompleter`](completion::TreeCompleter).
+pub struct Commander<R, S = ()> {
+    root: Arc<SubClass<R, S>>,
+    current: Arc<SubClass<R, S>>,
+    /// The classes above `current`, starting with the root.
+    parents: Vec<Arc<SubClass<R, S>>>,
+    path: String,
+    back_history: VecDeque<Position<R, S>>,
+    forward_history: Vec<Position<R, S>>,
+    dir_stack: VecDeque<Position<R, S>>,
+    error_chain: bool,
+    abbreviations: bool,
+    builtins: Builtins,
+    runtime_aliases: BTreeMap<String, String>,
+    state: Arc<RwLock<S>>,
+}
+
+const PATH_SEP: char = '.';
+
+/// The most classes kept in the back history and the class stack.
+const HISTORY_LIMIT: usize = 100;
+
+/// A position in the tree, the current class and the classes above it.
+struct Position<R, S> {
+    current: Arc<SubClass<R, S>>,
+    parents: Vec<Arc<SubClass<R, S>>>,
     path: String,
 }
 
-impl<R> Commander<R> {
+impl<R, S> Clone for Position<R, S> {
+    fn clone(&self) -> Self {
+        Position {
+            current: Arc::clone(&self.current),
+            parents: self.parents.clone(),
+            path: self.path.clone(),
+        }
+    }
+}
+
+impl<R, S> Commander<R, S> {
     /// Return the root name.
     ///
     /// # Example
@@ -172,6 +225,78 @@ impl<R> Commander<R> {
         self.current == self.root
     }
 
+    /// Return a reference to the user state.
+    ///
+    /// The state is locked while the guard is held, so it should not be held while parsing a line.
+    ///
+    /// # Example
+    /// ```rust
+    /// # use cmdtree::*;
+    /// let mut cmder = Builder::with_state("base", 0)
+    ///     .add_state_action("inc", "", |count, _wtr, _args| *count += 1)
+    ///     .into_commander().unwrap();
+    ///
+    /// cmder.parse_line("inc", true, &mut std::io::sink());
+    /// cmder.parse_line("inc", true, &mut std::io::sink());
+    /// assert_eq!(*cmder.state(), 2);
+    /// ```
+    pub fn state(&self) -> RwLockReadGuard<'_, S> {
+        self.state.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Return a mutable reference to the user state.
+    ///
+    /// The state is locked while the guard is held, so it should not be held while parsing a line.
+    pub fn state_mut(&mut self) -> RwLockWriteGuard<'_, S> {
+        self.state.write().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Consumes the `Commander`, returning the user state.
+    ///
+    /// Returns `None` if the state is still shared with a [`TreeCompleter`](completion::TreeCompleter).
+    pub fn into_state(self) -> Option<S> {
+        Arc::try_unwrap(self.state)
+            .ok()
+            .map(|x| x.into_inner().unwrap_or_else(PoisonError::into_inner))
+    }
+
+    /// Set whether the chain of error sources is written when a fallible action fails.
+    ///
+    /// By default only the error message is written. When set, each source of the error is written on a
+    /// following `caused by:` line.
+    pub fn set_error_chain(&mut self, show: bool) {
+        self.error_chain = show;
+    }
+
+    /// Set whether classes and actions can be abbreviated.
+    ///
+    /// By default words must match a class or action name exactly. When set, a word which is a prefix of
+    /// exactly one class or action name in the current class is accepted, so `sh int` can invoke `show interfaces`.
+    /// A prefix of several names results in `LineResult::Ambiguous`, listing the candidates.
+    ///
+    /// # Example
+    /// ```rust
+    /// # use cmdtree::*;
+    /// let mut cmder = Builder::default_config("base")
+    ///     .begin_class("show", "")
+    ///     .add_action("interfaces", "", |_, _| ())
+    ///     .add_action("info", "", |_, _| ())
+    ///     .into_commander().unwrap();
+    ///
+    /// cmder.set_abbreviations(true);
+    /// assert_eq!(cmder.parse_line("sh int", false, &mut std::io::sink()), LineResult::Action(()));
+    /// assert_eq!(
+    ///     cmder.parse_line("sh i", false, &mut std::io::sink()),
+    ///     LineResult::Ambiguous {
+    ///         word: "i".to_string(),
+    ///         candidates: vec!["interfaces".to_string(), "info".to_string()],
+    ///     }
+    /// );
+    /// ```
+    pub fn set_abbreviations(&mut self, allow: bool) {
+        self.abbreviations = allow;
+    }
+
     /// Run the `Commander` interactively.
     /// Consumes the instance, and blocks the thread until the loop is exited.
     ///
@@ -181,6 +306,55 @@ impl<R> Commander<R> {
         self.run_with_completion(|_| linefeed::complete::DummyCompleter)
     }
 
+    /// Run the `Commander` over any reader and writer.
+    /// Blocks the thread until an `exit` command is reached or the reader is exhausted.
+    ///
+    /// Unlike `run`, this does not require the `runnable` feature, so the same tree can be driven from
+    /// stdin pipes, test harnesses, or embedded consoles.
+    /// A prompt of the current path is written before each line is read,
+    /// and lines are parsed with [`try_parse_chain_with`](Commander::try_parse_chain_with) without colour,
+    /// treating any data returned from an action as a success.
+    ///
+    /// # Example
+    /// ```rust
+    /// # use cmdtree::*;
+    /// let mut cmder = Builder::default_config("base")
+    ///     .begin_class("one", "")
+    ///     .add_action("echo", "", |wtr, args| writeln!(wtr, "{}", args.join(" ")).unwrap())
+    ///     .into_commander().unwrap();
+    ///
+    /// let input = "one\necho hello\nexit\necho unreachable\n";
+    /// let mut output = Vec::new();
+    /// cmder.run_with_io(input.as_bytes(), &mut output).unwrap();
+    ///
+    /// assert_eq!(
+    ///     String::from_utf8(output).unwrap(),
+    ///     "base=> base.one=> hello\nbase.one=> "
+    /// );
+    /// ```
+    pub fn run_with_io<B: BufRead, W: Write>(
+        &mut self,
+        mut reader: B,
+        mut writer: W,
+    ) -> io::Result<()> {
+        let mut line = String::new();
+
+        loop {
+            write!(writer, "{}=> ", self.path())?;
+            writer.flush()?;
+
+            line.clear();
+            if reader.read_line(&mut line)? == 0 {
+                return Ok(()); // reader exhausted
+            }
+
+            let results = self.try_parse_chain_with(&line, false, &mut writer, |_| true)?;
+            if results.iter().any(|r| matches!(r, LineResult::Exit)) {
+                return Ok(());
+            }
+        }
+    }
+
     /// Returns the command structure as a sorted set.
     ///
     /// Can return from the the current class or the root.
@@ -219,6 +393,7 @@ impl<R> Commander<R> {
                     path: format!("..{}", action.name),
                     itemtype: ItemType::Action,
                     help_msg: action.help.clone(),
+                    args: action.args.clone(),
                 });
             }
 
@@ -233,6 +408,7 @@ impl<R> Commander<R> {
                     path: format!("{}..{}", parent_path, action.name),
                     itemtype: ItemType::Action,
                     help_msg: action.help.clone(),
+                    args: action.args.clone(),
                 });
             }
 
@@ -244,6 +420,7 @@ impl<R> Commander<R> {
                 path: parent_path,
                 itemtype: ItemType::Class,
                 help_msg: parent.help.clone(),
+                args: None,
             });
         }
 
@@ -251,68 +428,293 @@ impl<R> Commander<R> {
     }
 }
 
+impl<R, S> Commander<R, S> {
+    /// Replaces the builtins, failing if they would shadow a class or action, as in
+    /// [`with_builtins`](BuilderChain::with_builtins).
+    fn set_builtins(&mut self, builtins: Builtins) -> Result<(), BuildError> {
+        if let Some(name) = builtins.duplicate() {
+            return Err(BuildError::DuplicateBuiltin(name.to_string()));
+        }
+        if let Some(name) = builtins.invalid() {
+            return Err(BuildError::InvalidBuiltinName(name.to_string()));
+        }
+        self.root.check_tree(|n| builtins.find(n).is_some())?;
+        self.builtins = builtins;
+        Ok(())
+    }
+}
+
+/// Moving about the tree.
+impl<R, S> Commander<R, S> {
+    fn position(&self) -> Position<R, S> {
+        Position {
+            current: Arc::clone(&self.current),
+            parents: self.parents.clone(),
+            path: self.path.clone(),
+        }
+    }
+
+    fn set_position(&mut self, position: Position<R, S>) {
+        self.current = position.current;
+        self.parents = position.parents;
+        self.path = position.path;
+    }
+
+    fn enter_class(&mut self, class: Arc<SubClass<R, S>>) {
+        self.path.push(PATH_SEP);
+        self.path.push_str(&class.name);
+        let parent = std::mem::replace(&mut self.current, class);
+        self.parents.push(parent);
+    }
+
+    /// Moves to the parent class, staying put at the root.
+    fn leave_class(&mut self) {
+        if let Some(parent) = self.parents.pop() {
+            let len = self.path.len() - self.current.name.len() - PATH_SEP.len_utf8();
+            self.path.truncate(len);
+            self.current = parent;
+        }
+    }
+
+    fn goto_root(&mut self) {
+        self.current = Arc::clone(&self.root);
+        self.parents.clear();
+        self.path = self.root.name.clone();
+    }
+
+    /// Pushes `position` on the class stack, dropping the bottom class once the stack is full.
+    fn push_dir_stack(&mut self, position: Position<R, S>) {
+        if self.dir_stack.len() == HISTORY_LIMIT {
+            self.dir_stack.pop_front();
+        }
+        self.dir_stack.push_back(position);
+    }
+
+    /// Records `start` in the back history if the line moved away from it, clearing the forward history.
+    fn record_history(&mut self, start: Position<R, S>) {
+        if !Arc::ptr_eq(&start.current, &self.current) {
+            if self.back_history.len() == HISTORY_LIMIT {
+                self.back_history.pop_front();
+            }
+            self.back_history.push_back(start);
+            self.forward_history.clear();
+        }
+    }
+}
+
 #[derive(Debug, Eq)]
-struct SubClass<R> {
+struct SubClass<R, S> {
     name: String,
+    aliases: Vec<String>,
     help: CmdStr,
-    classes: Vec<Arc<SubClass<R>>>,
-    actions: Vec<Action<R>>,
+    classes: Vec<Arc<SubClass<R, S>>>,
+    actions: Vec<Action<R, S>>,
 }
 
-impl<R> SubClass<R> {
+impl<R, S> SubClass<R, S> {
     fn with_name<H: Into<CmdStr>>(name: &str, help_msg: H) -> Self {
         SubClass {
             name: name.to_lowercase(),
+            aliases: Vec::new(),
             help: help_msg.into(),
             classes: Vec::new(),
             actions: Vec::new(),
         }
     }
+
+    /// The name followed by the aliases.
+    fn names(&self) -> impl Iterator<Item = &str> {
+        std::iter::once(self.name.as_str()).chain(self.aliases.iter().map(String::as_str))
+    }
+
+    /// Returns an error if `matches` is true for a name of a class or action below this class, at any depth.
+    fn check_tree<F: Fn(&str) -> bool>(&self, matches: F) -> Result<(), BuildError> {
+        let mut stack = vec![self];
+        while let Some(class) = stack.pop() {
+            if class.actions.iter().flat_map(|a| a.names()).any(&matches) {
+                return Err(BuildError::NameExistsAsAction);
+            }
+            if class.classes.iter().flat_map(|c| c.names()).any(&matches) {
+                return Err(BuildError::NameExistsAsClass);
+            }
+            stack.extend(class.classes.iter().map(|c| &**c));
+        }
+        Ok(())
+    }
 }
 
-impl<R> PartialEq for SubClass<R> {
+impl<R, S> PartialEq for SubClass<R, S> {
     fn eq(&self, other: &Self) -> bool {
         self.name == other.name
+            && self.aliases == other.aliases
             && self.help == other.help
             && self.classes == other.classes
             && self.actions == other.actions
     }
 }
 
-type ClosureFn<R> = Box<dyn FnMut(&mut dyn Write, &[&str]) -> R + Send>;
-struct Action<R> {
+type ClosureFn<R, S> = Box<dyn FnMut(&mut ActionContext<S>) -> Result<R, CallError> + Send>;
+type ArgCompleterFn<S> = Arc<dyn Fn(&S, &[&str], &str) -> Vec<String> + Send + Sync>;
+struct Action<R, S> {
     name: String,
+    aliases: Vec<String>,
     help: CmdStr,
-    closure: Mutex<ClosureFn<R>>,
+    args: Option<args::ArgSpec>,
+    completer: Option<ArgCompleterFn<S>>,
+    closure: Mutex<ClosureFn<R, S>>,
 }
 
-impl<R> Action<R> {
-    fn call<W: Write>(&self, wtr: &mut W, arguments: &[&str]) -> R {
+impl<R, S> Action<R, S> {
+    /// The name followed by the aliases.
+    fn names(&self) -> impl Iterator<Item = &str> {
+        std::iter::once(self.name.as_str()).chain(self.aliases.iter().map(String::as_str))
+    }
+
+    fn call(&self, ctx: &mut ActionContext<S>) -> Result<R, CallError> {
+        ctx.parsed = self
+            .args
+            .as_ref()
+            .map(|spec| spec.parse(ctx.args))
+            .transpose()?;
         let c = &mut *self.closure.lock().expect("locking command action failed");
-        c(wtr, arguments)
+        c(ctx)
+    }
+}
+
+/// The reasons invoking an action can fail.
+#[derive(Debug)]
+enum CallError {
+    /// The arguments did not match the argument specification, the closure was not invoked.
+    Args(args::ArgError),
+    /// A fallible action returned an error.
+    Action(ActionError),
+}
+
+impl From<args::ArgError> for CallError {
+    fn from(e: args::ArgError) -> Self {
+        CallError::Args(e)
+    }
+}
+
+/// The context of an action invocation.
+///
+/// Passed to actions added with [`add_context_action`](builder::BuilderChain::add_context_action).
+/// Exposes the writer, arguments and user state, along with where and how the action was invoked.
+///
+/// # Example
+/// ```rust
+/// # use cmdtree::*;
+/// let mut cmder = Builder::default_config("base")
+///     .begin_class("one", "")
+///     .add_context_action("whereami", "", |ctx| {
+///         assert_eq!(ctx.path(), "base.one");
+///         assert_eq!(ctx.qualified_path(), "one..whereami");
+///         assert_eq!(ctx.line(), "one whereami now");
+///         assert_eq!(ctx.args(), &["now"]);
+///         assert!(!ctx.colourise());
+///         let path = ctx.path().to_string();
+///         writeln!(ctx.writer(), "invoked from {}", path).unwrap();
+///     })
+///     .into_commander().unwrap();
+///
+/// cmder.parse_line("one whereami now", false, &mut std::io::sink());
+/// ```
+pub struct ActionContext<'a, S> {
+    writer: &'a mut dyn Write,
+    args: &'a [&'a str],
+    state: &'a mut S,
+    root_name: &'a str,
+    path: &'a str,
+    qualified_path: String,
+    line: &'a str,
+    colourise: bool,
+    parsed: Option<args::Args>,
+}
+
+impl<'a, S> ActionContext<'a, S> {
+    /// The writer passed to `parse_line`.
+    pub fn writer(&mut self) -> &mut dyn Write {
+        self.writer
+    }
+
+    /// The arguments after the action command.
+    pub fn args(&self) -> &[&str] {
+        self.args
+    }
+
+    /// The user state owned by the `Commander`.
+    pub fn state(&self) -> &S {
+        self.state
+    }
+
+    /// The mutable user state owned by the `Commander`.
+    pub fn state_mut(&mut self) -> &mut S {
+        self.state
+    }
+
+    /// The root name of the `Commander`.
+    pub fn root_name(&self) -> &str {
+        self.root_name
+    }
+
+    /// The path of the class the action belongs to, separated by `.`, as in [`Commander::path`].
+    pub fn path(&self) -> &str {
+        self.path
+    }
+
+    /// The qualified action path from root, in the format produced by [`Commander::structure`].
+    /// eg `a.nested..action`
+    pub fn qualified_path(&self) -> &str {
+        &self.qualified_path
+    }
+
+    /// The raw input the action was invoked from.
+    ///
+    /// For a chained line, such as `a; b && c`, this is the text of the segment which invoked the action.
+    pub fn line(&self) -> &str {
+        self.line
+    }
+
+    /// Whether output should be colourised, as passed to `parse_line`.
+    pub fn colourise(&self) -> bool {
+        self.colourise
+    }
+
+    /// The arguments parsed against the action's argument specification.
+    ///
+    /// This is `None` unless the action was added with an [`ArgSpec`](args::ArgSpec),
+    /// such as with [`add_context_action_with_args`](builder::BuilderChain::add_context_action_with_args).
+    pub fn parsed_args(&self) -> Option<&args::Args> {
+        self.parsed.as_ref()
     }
 }
 
-impl Action<()> {
+impl Action<(), ()> {
     #[cfg(test)]
     fn blank_fn<H: Into<CmdStr>>(name: &str, help_msg: H) -> Self {
         Action {
             name: name.to_lowercase(),
+            aliases: Vec::new(),
             help: help_msg.into(),
-            closure: Mutex::new(Box::new(|_, _| ())),
+            args: None,
+            completer: None,
+            closure: Mutex::new(Box::new(|_| Ok(()))),
         }
     }
 }
 
-impl<R> PartialEq for Action<R> {
+impl<R, S> PartialEq for Action<R, S> {
     fn eq(&self, other: &Self) -> bool {
-        self.name == other.name && self.help == other.help
+        self.name == other.name
+            && self.aliases == other.aliases
+            && self.help == other.help
+            && self.args == other.args
     }
 }
 
-impl<R> Eq for Action<R> {}
+impl<R, S> Eq for Action<R, S> {}
 
-impl<R> fmt::Debug for Action<R> {
+impl<R, S> fmt::Debug for Action<R, S> {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "Action {{ name: {}, help: {} }}", self.name, self.help)
     }
@@ -330,6 +732,8 @@ pub struct StructureInfo {
     pub itemtype: ItemType,
     /// The help message.
     pub help_msg: CmdStr,
+    /// The argument specification of an action, if it was added with one.
+    pub args: Option<args::ArgSpec>,
 }
 
 impl PartialEq for StructureInfo {
@@ -413,7 +817,7 @@ mod tests {
 
     #[test]
     fn subclass_with_name_test() {
-        let sc = SubClass::<()>::with_name("NAME", "Help Message");
+        let sc = SubClass::<(), ()>::with_name("NAME", "Help Message");
         assert_eq!(&sc.name, "name");
         assert_eq!(sc.help.as_str(), "Help Message");
     }
@@ -459,13 +863,201 @@ mod tests {
 
         let w = &mut std::io::sink();
 
-        assert_eq!(cmder.at_root(), true);
+        assert!(cmder.at_root());
 
         cmder.parse_line("one two", true, w);
-        assert_eq!(cmder.at_root(), false);
+        assert!(!cmder.at_root());
 
         cmder.parse_line("c", true, w);
-        assert_eq!(cmder.at_root(), true);
+        assert!(cmder.at_root());
+
+        cmder.parse_line("one two", true, w);
+        cmder.parse_line("/", true, w);
+        assert!(cmder.at_root());
+    }
+
+    #[test]
+    fn state_test() {
+        let mut cmder = Builder::with_state("base", Vec::new())
+            .begin_class("db", "")
+            .add_state_action("push", "", |v: &mut Vec<String>, _, args| {
+                v.extend(args.iter().map(|x| x.to_string()));
+                v.len()
+            })
+            .end_class()
+            .add_state_action("clear", "", |v: &mut Vec<String>, _, _| {
+                v.clear();
+                0
+            })
+            .add_action("stateless", "", |_, _| 0)
+            .into_commander()
+            .unwrap();
+
+        let w = &mut std::io::sink();
+
+        assert_eq!(
+            cmder.parse_line("db push a b", true, w),
+            LineResult::Action(2)
+        );
+        assert_eq!(
+            cmder.parse_line("db push c", true, w),
+            LineResult::Action(3)
+        );
+        assert_eq!(*cmder.state(), ["a", "b", "c"]);
+        assert_eq!(
+            cmder.parse_line("stateless", true, w),
+            LineResult::Action(0)
+        );
+        cmder.state_mut().pop();
+        assert_eq!(
+            cmder.parse_line("db push d", true, w),
+            LineResult::Action(3)
+        );
+        assert_eq!(cmder.into_state().unwrap(), vec!["a", "b", "d"]);
+    }
+
+    #[test]
+    fn set_builtins_names_test() {
+        let mut cmder = Builder::default_config("base")
+            .begin_class("one", "")
+            .add_action("a", "", |_, _| ())
+            .into_commander()
+            .unwrap();
+
+        let mut set = |name: &str| {
+            cmder.set_builtins(Builtins::default().set_names(builtins::Builtin::Root, &[name]))
+        };
+        let err = |name: &str| Err(BuildError::InvalidBuiltinName(name.to_string()));
+        assert_eq!(set(""), err(""));
+        assert_eq!(set("to\troot"), err("to\troot"));
+        assert_eq!(set("/."), err("/."));
+        assert_eq!(set("~"), Ok(()));
+
+        assert_eq!(
+            cmder.parse_line("one a", false, &mut std::io::sink()),
+            LineResult::Action(())
+        );
+    }
+
+    #[test]
+    fn action_context_test() {
+        let mut cmder = Builder::with_state("base", 0)
+            .add_context_action("root-action", "", |ctx| {
+                *ctx.state_mut() += 1;
+                Some((ctx.qualified_path().to_string(), ctx.path().to_string()))
+            })
+            .begin_class("one", "")
+            .begin_class("two", "")
+            .add_context_action("action", "", |ctx| {
+                *ctx.state_mut() += 1;
+                Some((ctx.qualified_path().to_string(), ctx.line().to_string()))
+            })
+            .into_commander()
+            .unwrap();
+
+        let w = &mut std::io::sink();
+
+        assert_eq!(
+            cmder.parse_line("root-action", true, w),
+            LineResult::Action(Some(("..root-action".to_string(), "base".to_string())))
+        );
+        // the line of a chained action is its own segment
+        assert_eq!(
+            cmder.parse_chain("one; two action  'a;b' && two action", true, w),
+            vec![
+                LineResult::Class,
+                LineResult::Action(Some((
+                    "one.two..action".to_string(),
+                    "two action  'a;b'".to_string()
+                ))),
+                LineResult::Action(Some((
+                    "one.two..action".to_string(),
+                    "two action".to_string()
+                )))
+            ]
+        );
+        assert_eq!(*cmder.state(), 3);
+    }
+
+    #[test]
+    fn action_context_with_args_test() {
+        use args::{ArgSpec, ArgType};
+
+        let mut cmder = Builder::with_state("base", 0i64)
+            .add_context_action("plain", "", |ctx| ctx.parsed_args().is_some())
+            .add_context_action_with_args(
+                "add",
+                "",
+                ArgSpec::new().required("n", ArgType::Int),
+                |ctx| {
+                    let n = ctx.parsed_args().and_then(|a| a.get_int("n")).unwrap();
+                    if n < 0 {
+                        return Err("negative");
+                    }
+                    *ctx.state_mut() += n;
+                    Ok(true)
+                },
+            )
+            .into_commander()
+            .unwrap();
+
+        let w = &mut std::io::sink();
+
+        assert_eq!(
+            cmder.parse_line("plain 1", false, w),
+            LineResult::Action(false)
+        );
+        assert_eq!(
+            cmder.parse_line("add 2", false, w),
+            LineResult::Action(true)
+        );
+        assert_eq!(
+            cmder.parse_line("add 3", false, w),
+            LineResult::Action(true)
+        );
+        match cmder.parse_line("add x", false, w) {
+            LineResult::InvalidArgs(_) => (),
+            r => panic!("expected invalid args, got {:?}", r),
+        }
+        match cmder.parse_line("add -- -1", false, w) {
+            LineResult::ActionError(e) => assert_eq!(e.to_string(), "negative"),
+            r => panic!("expected an action error, got {:?}", r),
+        }
+        assert_eq!(*cmder.state(), 5);
+    }
+
+    #[test]
+    fn run_with_io_test() {
+        let mut cmder = Builder::default_config("base")
+            .begin_class("one", "")
+            .add_action("echo", "", |wtr, args| {
+                writeln!(wtr, "{}", args.join(" ")).unwrap()
+            })
+            .into_commander()
+            .unwrap();
+
+        let mut output = Vec::new();
+        cmder
+            .run_with_io("one echo a\r\none\n\necho b; c".as_bytes(), &mut output)
+            .unwrap();
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "base=> a\nbase=> base.one=> base.one=> b\nbase=> "
+        );
+        assert!(cmder.at_root());
+
+        // action data need not implement ActionOutcome
+        let mut cmder = Builder::new("base")
+            .add_action("name", "", |_, args| args.join(" "))
+            .into_commander()
+            .unwrap();
+
+        let mut output = Vec::new();
+        cmder
+            .run_with_io("name a && name b || name c\n".as_bytes(), &mut output)
+            .unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), "base=> base=> ");
     }
 
     #[test]
@@ -0,0 +1,224 @@
+/// Build a command tree with nested blocks, expanding to `Builder` calls.
+///
+/// The tree is given as the root name and a block, or a `Builder` expression, `=>`, and a block.
+/// Within a block:
+///
+/// - `class "name" { ... }` or `class "name": "help" { ... }` begins a class, which is ended with the block.
+///   The name can also be a macro call such as `concat!("a", "b")`.
+/// - `action "name" => closure;` or `action "name": "help" => closure;` adds an action, as with `add_action`.
+/// - Any other [`BuilderChain`](crate::BuilderChain) method can be called by name and ended with
+///   a `;`, such as `add_state_action("inc", "", |n, _, _| *n += 1);` or `add_alias("name", "alias");`.
+///
+/// Classes are only opened and closed by their blocks, so calling `begin_class`, `end_class`,
+/// `root` or `into_commander` within a block is a compile error and the nesting cannot be unbalanced.
+/// The macro evaluates to a `BuilderResult`.
+///
+/// # Example
+/// ```rust
+/// use cmdtree::*;
+///
+/// let mut cmder = cmdtree! {
+///     "base" {
+///         class "print": "pertains to printing stuff" {
+///             action "echo": "repeat stuff" => |wtr, args| writeln!(wtr, "{}", args.join(" ")).is_ok();
+///             add_alias("echo", "say");
+///         }
+///         class "empty" {}
+///         action "ping" => |_, _| true;
+///     }
+/// }
+/// .into_commander()
+/// .unwrap();
+///
+/// assert_eq!(cmder.parse_line("print say hi", false, &mut std::io::sink()), LineResult::Action(true));
+///
+/// let mut cmder = cmdtree! {
+///     Builder::with_state("counter", 0) => {
+///         add_state_action("inc", "increments the count", |n, _, _| *n += 1);
+///     }
+/// }
+/// .into_commander()
+/// .unwrap();
+///
+/// cmder.parse_line("inc", false, &mut std::io::sink());
+/// assert_eq!(*cmder.state(), 1);
+/// ```
+///
+/// Closing a class without a block does not compile:
+/// ```rust,compile_fail
+/// use cmdtree::*;
+///
+/// let built: builder::BuilderResult<()> = cmdtree! {
+///     "base" {
+///         class "a" {
+///             end_class();
+///         }
+///     }
+/// };
+/// ```
+///
+/// Neither does leaving out the `;` after a method call:
+/// ```rust,compile_fail
+/// use cmdtree::*;
+///
+/// let built: builder::BuilderResult<()> = cmdtree! {
+///     "base" {
+///         add_alias("a", "b")
+///         action "a" => |_, _| ();
+///     }
+/// };
+/// ```
+#[macro_export]
+macro_rules! cmdtree {
+    // each item of a block is a statement, so only nested classes recurse.
+    // the `;` after an item is passed on through the never-matched `$never` so that
+    // method calls missing it can be rejected.
+    (@block $b:expr; $(
+        $kw:ident $head:tt $(! $margs:tt)? $(: $help:literal)? $({ $($inner:tt)* })?
+        $(=> $closure:expr;)? $(; $($never:lifetime)?)?
+    )*) => {{
+        let builder = $b;
+        $(
+            let builder = $crate::cmdtree!(@item builder;
+                $kw $head $(! $margs)? $(: $help)? $({ $($inner)* })? $(=> $closure)?
+                $(; $($never)?)?
+            );
+        )*
+        builder
+    }};
+    (@item $b:expr; class $name:tt $(! $margs:tt)? { $($inner:tt)* } $(;)?) => {
+        $crate::cmdtree!(@item $b; class $name $(! $margs)?: "" { $($inner)* })
+    };
+    (@item $b:expr; class $name:tt $(! $margs:tt)? : $help:literal { $($inner:tt)* } $(;)?) => {
+        $crate::cmdtree!(@block $b.begin_class($name $(! $margs)?, $help); $($inner)*).end_class()
+    };
+    (@item $b:expr; action $name:tt $(! $margs:tt)? => $closure:expr $(;)?) => {
+        $crate::cmdtree!(@item $b; action $name $(! $margs)?: "" => $closure)
+    };
+    (@item $b:expr; action $name:tt $(! $margs:tt)? : $help:literal => $closure:expr $(;)?) => {
+        $b.add_action($name $(! $margs)?, $help, $closure)
+    };
+    (@item $b:expr; begin_class $($rest:tt)*) => {
+        compile_error!("use a `class \"name\" { ... }` block instead of `begin_class`")
+    };
+    (@item $b:expr; end_class $($rest:tt)*) => {
+        compile_error!("classes are ended by their blocks, `end_class` cannot be called")
+    };
+    (@item $b:expr; root $($rest:tt)*) => {
+        compile_error!("classes are ended by their blocks, `root` cannot be called")
+    };
+    (@item $b:expr; into_commander $($rest:tt)*) => {
+        compile_error!("`into_commander` must be called on the result of the macro")
+    };
+    (@item $b:expr; $method:ident ( $($args:tt)* ) ;) => {
+        $b.$method($($args)*)
+    };
+    (@item $b:expr; $method:ident ( $($args:tt)* )) => {
+        compile_error!(concat!("expected `;` after `", stringify!($method), "(...)`"))
+    };
+    ($root:literal { $($body:tt)* }) => {
+        $crate::cmdtree!($crate::Builder::new($root) => { $($body)* })
+    };
+    ($builder:expr => { $($body:tt)* }) => {{
+        #[allow(unused_imports)]
+        use $crate::BuilderChain;
+        let builder: $crate::builder::BuilderResult<_, _> = Ok($builder);
+        $crate::cmdtree!(@block builder; $($body)*)
+    }};
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::*;
+
+    #[test]
+    fn cmdtree_macro_test() {
+        let built = cmdtree! {
+            "base" {
+                class "one": "one help" {
+                    class "two" {
+                        action "deep" => |_, _| 2;
+                    }
+                    action "shallow": "shallow help" => |_, args| args.len();
+                }
+                add_alias("one", "1");
+                action "top" => |_, _| 0;
+            }
+        };
+
+        let chained = Builder::new("base")
+            .begin_class("one", "one help")
+            .begin_class("two", "")
+            .add_action("deep", "", |_, _| 2)
+            .end_class()
+            .add_action("shallow", "shallow help", |_, args| args.len())
+            .end_class()
+            .add_alias("one", "1")
+            .add_action("top", "", |_, _| 0);
+
+        assert_eq!(built, chained);
+
+        let mut cmder = built.into_commander().unwrap();
+        let w = &mut std::io::sink();
+        assert_eq!(
+            cmder.parse_line("1 two deep", false, w),
+            LineResult::Action(2)
+        );
+        assert_eq!(
+            cmder.parse_line("one shallow a b", false, w),
+            LineResult::Action(2)
+        );
+    }
+
+    #[test]
+    fn cmdtree_macro_errors_test() {
+        let empty: builder::BuilderResult<()> = cmdtree!("base" {});
+        assert!(empty.is_ok());
+
+        let duplicate: builder::BuilderResult<()> = cmdtree! {
+            "base" {
+                class "one" {}
+                action "one" => |_, _| ();
+            }
+        };
+        assert_eq!(duplicate, Err(BuildError::NameExistsAsClass));
+    }
+
+    #[test]
+    fn cmdtree_macro_large_block_test() {
+        // four items for each of 36 names and a class, 145 items in a block,
+        // more than the default recursion limit of 128
+        macro_rules! large {
+            ($($name:literal)*) => {
+                cmdtree! {
+                    "base" {
+                        $(
+                            action $name => |_, _| 0;
+                            add_action(concat!($name, "-1"), "", |_, _| 1);
+                            add_alias(concat!($name, "-1"), concat!($name, "-a"));
+                            class concat!($name, "-c") {}
+                        )*
+                        class "nested" {
+                            action "deep" => |_, _| 3;
+                        }
+                    }
+                }
+            };
+        }
+
+        let built: builder::BuilderResult<usize> = large!(
+            "n0" "n1" "n2" "n3" "n4" "n5" "n6" "n7" "n8" "n9" "n10" "n11"
+            "n12" "n13" "n14" "n15" "n16" "n17" "n18" "n19" "n20" "n21" "n22" "n23"
+            "n24" "n25" "n26" "n27" "n28" "n29" "n30" "n31" "n32" "n33" "n34" "n35"
+        );
+        let mut cmder = built.into_commander().unwrap();
+        let w = &mut std::io::sink();
+        assert_eq!(cmder.parse_line("n35-1", false, w), LineResult::Action(1));
+        assert_eq!(cmder.parse_line("n35-a", false, w), LineResult::Action(1));
+        assert_eq!(
+            cmder.parse_line("nested deep", false, w),
+            LineResult::Action(3)
+        );
+        assert_eq!(cmder.parse_line("n0-c", false, w), LineResult::Class);
+    }
+}
@@ -1,19 +1,39 @@
 use super::*;
+use args::ArgError;
 use colored::*;
+use std::error::Error;
 use std::io::{self, Write};
 
-const PATH_SEP: char = '.';
-
 #[derive(Debug, PartialEq)]
-enum WordResult<'a, R> {
-    Help(&'a SubClass<R>),
+enum WordResult<'a, R, S> {
+    Help(&'a SubClass<R, S>),
     Cancel,
     Exit,
-    Class(&'a Arc<SubClass<R>>),
-    Action(&'a Action<R>),
+    Class(&'a Arc<SubClass<R, S>>),
+    Action(&'a Action<R, S>),
+    Up,
+    Root,
+    /// An abbreviation matching the names of several classes or actions.
+    Ambiguous(Vec<String>),
     Unrecognized,
 }
 
+/// A step of a path given as a single word.
+enum PathStep<'a> {
+    Root,
+    Word(&'a str),
+}
+
+/// What words resolve to from a class, as `parse_line` would run them.
+pub(crate) enum Resolved<R, S> {
+    /// The words move to the class.
+    Class(Arc<SubClass<R, S>>),
+    /// The words invoke the action at the index in the class, with the remaining words as its arguments.
+    Action(Arc<SubClass<R, S>>, usize, Vec<String>),
+    /// The words run a builtin which does not move to a class, or do not resolve.
+    Other,
+}
+
 /// The result variants of `parse_line`.
 #[derive(Debug, PartialEq)]
 pub enum LineResult<R> {
@@ -29,7 +49,34 @@ pub enum LineResult<R> {
     /// The inner data is the returned data from invocation.
     Action(R),
     /// No commands recognised.
-    Unrecognized,
+    Unrecognized {
+        /// The word that did not match.
+        word: String,
+        /// Close matches among the classes, actions and builtins, closest first.
+        suggestions: Vec<String>,
+    },
+    /// A fallible action returned an error.
+    ActionError(ActionError),
+    /// The arguments did not match the action's argument specification.
+    /// The action was not invoked.
+    InvalidArgs(ArgError),
+    /// An abbreviated word matched several classes or actions.
+    /// Only returned when abbreviations are enabled with [`set_abbreviations`](Commander::set_abbreviations).
+    Ambiguous {
+        /// The abbreviated word.
+        word: String,
+        /// The names of the matching classes and actions.
+        candidates: Vec<String>,
+    },
+    /// `alias` or `unalias` command reached.
+    /// Only returned when enabled with [`set_alias_builtin`](Commander::set_alias_builtin).
+    Alias,
+    /// `back`, `forward` or `popd` command reached with no class to return to.
+    /// Only returned when enabled with [`set_history_builtins`](Commander::set_history_builtins).
+    NoHistory,
+    /// The line contained a quote that was never closed.
+    /// The inner data is the opening quote character.
+    UnterminatedQuote(char),
 }
 
 impl<R> LineResult<R> {
@@ -41,13 +88,163 @@ impl<R> LineResult<R> {
             _ => None,
         }
     }
+
+    /// As [`is_success`](LineResult::is_success), deferring to `action` for the data returned from an action.
+    fn succeeded<F: Fn(&R) -> bool>(&self, action: F) -> bool {
+        match self {
+            LineResult::Action(r) => action(r),
+            LineResult::Unrecognized { .. }
+            | LineResult::Ambiguous { .. }
+            | LineResult::ActionError(_)
+            | LineResult::InvalidArgs(_)
+            | LineResult::NoHistory
+            | LineResult::UnterminatedQuote(_) => false,
+            _ => true,
+        }
+    }
+}
+
+impl<R: ActionOutcome> LineResult<R> {
+    /// Whether the line is considered successful, used to gate `&&` and `||` in `parse_chain`.
+    ///
+    /// Actions defer to the [`ActionOutcome`] of their returned data. An unrecognised command, an action error,
+    /// invalid arguments, an empty history, or an unterminated quote is a failure, all other results are a success.
+    pub fn is_success(&self) -> bool {
+        self.succeeded(R::is_success)
+    }
+
+    /// The process exit code for the result, as used by [`run_args`](Commander::run_args).
+    ///
+    /// A successful result is `0`, a failed action or action error is `1`, and a usage error,
+    /// such as an unrecognised command, invalid arguments, or stopping at a class without an action, is `2`.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            LineResult::Action(r) if r.is_success() => 0,
+            LineResult::Action(_) | LineResult::ActionError(_) => 1,
+            LineResult::Class
+            | LineResult::Unrecognized { .. }
+            | LineResult::Ambiguous { .. }
+            | LineResult::InvalidArgs(_)
+            | LineResult::NoHistory
+            | LineResult::UnterminatedQuote(_) => 2,
+            _ => 0,
+        }
+    }
+}
+
+/// The error returned from a fallible action.
+///
+/// Wraps the boxed error so it can be carried in a `LineResult`.
+/// Two `ActionError`s are equal if their messages and sources' messages are equal.
+#[derive(Debug)]
+pub struct ActionError {
+    inner: Box<dyn Error + Send + Sync>,
+}
+
+impl ActionError {
+    /// Wrap an error.
+    pub fn new<E: Into<Box<dyn Error + Send + Sync>>>(error: E) -> Self {
+        Self {
+            inner: error.into(),
+        }
+    }
+
+    /// The wrapped error.
+    pub fn inner(&self) -> &(dyn Error + Send + Sync + 'static) {
+        &*self.inner
+    }
+
+    /// Consumes the `ActionError`, returning the wrapped error.
+    pub fn into_inner(self) -> Box<dyn Error + Send + Sync> {
+        self.inner
+    }
+
+    /// Iterates through the sources of the wrapped error, starting with the direct source.
+    pub fn sources(&self) -> impl Iterator<Item = &(dyn Error + 'static)> {
+        std::iter::successors(self.inner.source(), |e| (*e).source())
+    }
+}
+
+impl fmt::Display for ActionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.inner)
+    }
+}
+
+impl PartialEq for ActionError {
+    fn eq(&self, other: &Self) -> bool {
+        self.to_string() == other.to_string()
+            && self
+                .sources()
+                .map(|e| e.to_string())
+                .eq(other.sources().map(|e| e.to_string()))
+    }
+}
+
+/// Determines whether the data returned from an action represents a success.
+///
+/// Used when chaining commands with `&&` and `||` in [`parse_chain`](Commander::parse_chain).
+/// The run loops do not require it, and treat any data returned from an action as a success.
+pub trait ActionOutcome {
+    /// Returns `true` if the action succeeded.
+    fn is_success(&self) -> bool;
+}
+
+impl ActionOutcome for () {
+    fn is_success(&self) -> bool {
+        true
+    }
+}
+
+impl ActionOutcome for bool {
+    fn is_success(&self) -> bool {
+        *self
+    }
+}
+
+impl<T> ActionOutcome for Option<T> {
+    fn is_success(&self) -> bool {
+        self.is_some()
+    }
 }
 
-impl<R> Commander<R> {
+impl<T, E> ActionOutcome for Result<T, E> {
+    fn is_success(&self) -> bool {
+        self.is_ok()
+    }
+}
+
+/// How a command segment is joined to the previous segment.
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum Chain {
+    /// `;`, always run.
+    Always,
+    /// `&&`, run if the previous segment succeeded.
+    And,
+    /// `||`, run if the previous segment failed.
+    Or,
+}
+
+#[derive(Debug, PartialEq)]
+enum Token {
+    Word(String),
+    /// An operator and its byte range in the line.
+    Op(Chain, std::ops::Range<usize>),
+}
+
+impl<R, S> Commander<R, S> {
     /// Parse a line of commands and updates the `Commander` state.
     ///
     /// Parsing a line is akin to sending an input line to the commander in the run loop.
-    /// Commands are space separated, and executed within this function, any actions that are specified will be invoked.
+    /// Commands are whitespace separated, and executed within this function, any actions that are specified will be invoked.
+    ///
+    /// Words can be grouped using single or double quotes, and a backslash escapes the following character.
+    /// Inside single quotes every character is literal, inside double quotes only `\"` and `\\` are escapes.
+    /// A line with an unclosed quote results in `LineResult::UnterminatedQuote`.
+    ///
+    /// `..` moves up to the parent class and `/` moves to the root class. A path can be given as a single word,
+    /// with class names separated by `.`, such as `one.two`, and is addressed from the root when prefixed
+    /// with `/` or the root name, such as `/one.two` or `base.one.two`.
     ///
     /// Most branches result in a `LineResult::Continue` apart from an exit command which will result in a `LineResult::Exit`.
     /// It is up to the developer to decide on the behaviour.
@@ -65,246 +262,2029 @@ impl<R> Commander<R> {
     ///    cmder.parse_line("one two", true,  &mut std::io::sink());
     ///    assert_eq!(cmder.path(), "base.one.two");
     /// cmder.parse_line("echo Hello, world!", true, &mut std::io::sink());    // should print "Hello, world!"
+    /// cmder.parse_line("echo \"Hello,   world!\"", true, &mut std::io::sink());    // should print "Hello,   world!"
     /// ```
+    ///
+    /// # Panics
+    /// Panics if writing to `writer` fails. Use [`try_parse_line`](Commander::try_parse_line) to handle the error instead.
     pub fn parse_line<W: Write>(
         &mut self,
         line: &str,
         colourise: bool,
         writer: &mut W,
     ) -> LineResult<R> {
-        let line = line.replace("\n", "").replace("\r", "");
-        let words: Vec<_> = line.trim().split(' ').collect();
-        let mut idx = 0;
-        let mut words_iter = words.iter();
-        let mut next_word = words_iter.next();
+        self.try_parse_line(line, colourise, writer)
+            .expect("failed writing output to writer")
+    }
+
+    /// Parse a line of commands and updates the `Commander` state, returning any error from writing to `writer`.
+    ///
+    /// Behaves as [`parse_line`](Commander::parse_line), but does not panic if the writer fails, such as with a closed pipe or socket.
+    /// The `Commander` is left at the class it would be at if the write had succeeded.
+    ///
+    /// # Example
+    /// ```rust
+    /// use cmdtree::*;
+    /// use std::io::{self, Write};
+    ///
+    /// struct Closed;
+    /// impl Write for Closed {
+    ///     fn write(&mut self, _: &[u8]) -> io::Result<usize> {
+    ///         Err(io::Error::new(io::ErrorKind::BrokenPipe, "closed"))
+    ///     }
+    ///     fn flush(&mut self) -> io::Result<()> {
+    ///         Ok(())
+    ///     }
+    /// }
+    ///
+    /// let mut cmder = Builder::default_config("base").into_commander().unwrap();
+    ///
+    /// let err = cmder.try_parse_line("help", false, &mut Closed).unwrap_err();
+    /// assert_eq!(err.kind(), io::ErrorKind::BrokenPipe);
+    /// ```
+    pub fn try_parse_line<W: Write>(
+        &mut self,
+        line: &str,
+        colourise: bool,
+        writer: &mut W,
+    ) -> io::Result<LineResult<R>> {
+        match tokenize(line) {
+            Ok(words) => self.parse_words(line, &words, colourise, writer),
+            Err(quote) => unterminated_quote(quote, colourise, writer),
+        }
+    }
 
-        // if there is no current class, use the root
-        let start_class = Arc::clone(&self.current);
-        let start_path = self.path.clone();
+    /// Parse a line of chained commands and updates the `Commander` state.
+    ///
+    /// The line is split into segments on `;`, `&&` and `||`, with each segment parsed as in `parse_line`.
+    /// A segment following `;` is always run, a segment following `&&` is only run if the previous segment succeeded,
+    /// and a segment following `||` is only run if the previous segment failed.
+    /// Success is determined by [`LineResult::is_success`].
+    /// Operators inside quotes or escaped with a backslash are treated as normal characters.
+    ///
+    /// The results of the segments that were run are returned in order.
+    /// Reaching an `exit` command stops the chain.
+    ///
+    /// # Example
+    /// ```rust
+    /// use cmdtree::*;
+    /// let mut cmder = Builder::new("base")
+    ///     .begin_class("db", "")
+    ///     .add_action("connect", "", |_wtr, _args| false)
+    ///     .add_action("migrate", "", |_wtr, _args| true)
+    ///     .into_commander().unwrap();
+    ///
+    /// let results = cmder.parse_chain("db connect && db migrate; db migrate", true, &mut std::io::sink());
+    /// assert_eq!(results, vec![LineResult::Action(false), LineResult::Action(true)]);
+    /// ```
+    ///
+    /// # Panics
+    /// Panics if writing to `writer` fails. Use [`try_parse_chain`](Commander::try_parse_chain) to handle the error instead.
+    pub fn parse_chain<W: Write>(
+        &mut self,
+        line: &str,
+        colourise: bool,
+        writer: &mut W,
+    ) -> Vec<LineResult<R>>
+    where
+        R: ActionOutcome,
+    {
+        self.try_parse_chain(line, colourise, writer)
+            .expect("failed writing output to writer")
+    }
 
-        while let Some(word) = next_word {
-            idx += 1;
-            next_word = match parse_word(&self.current, word) {
-                WordResult::Help(sc) => {
-                    if colourise {
-                        write_help_coloured(&sc, writer).expect("failed writing output to writer");
-                    } else {
-                        write_help(&sc, writer).expect("failed writing output to writer");
-                    }
-                    self.current = Arc::clone(&start_class);
-                    self.path = start_path;
-                    return LineResult::Help;
-                }
-                WordResult::Cancel => {
-                    self.current = Arc::clone(&self.root);
-                    self.path = self.root.name.clone();
-                    return LineResult::Cancel;
-                }
-                WordResult::Exit => {
-                    return LineResult::Exit;
-                }
-                WordResult::Class(sc) => {
-                    self.path.push_str(&format!("{}{}", PATH_SEP, sc.name));
-                    self.current = Arc::clone(&sc);
-                    words_iter.next()
-                }
-                WordResult::Action(a) => {
-                    let slice = &words[idx..];
-                    let r = a.call(writer, slice);
-                    self.current = Arc::clone(&start_class);
-                    self.path = start_path;
-                    return LineResult::Action(r);
-                }
-                WordResult::Unrecognized => {
-                    let mut s = format!(
-                        "'{}' does not match any keywords, classes, or actions",
-                        word
-                    )
-                    .bright_red();
-
-                    if !colourise {
-                        s = s.white();
-                    }
+    /// Parse a line of chained commands and updates the `Commander` state, returning any error from writing to `writer`.
+    ///
+    /// Behaves as [`parse_chain`](Commander::parse_chain), but does not panic if the writer fails.
+    /// A write error stops the chain, the results of segments already run are discarded.
+    pub fn try_parse_chain<W: Write>(
+        &mut self,
+        line: &str,
+        colourise: bool,
+        writer: &mut W,
+    ) -> io::Result<Vec<LineResult<R>>>
+    where
+        R: ActionOutcome,
+    {
+        self.try_parse_chain_with(line, colourise, writer, R::is_success)
+    }
 
-                    writeln!(writer, "{}", s).expect("failed writing output to writer");
-                    self.current = Arc::clone(&start_class);
-                    self.path = start_path;
-                    return LineResult::Unrecognized;
-                }
+    /// Parse a line of chained commands as [`try_parse_chain`](Commander::try_parse_chain),
+    /// with `is_success` determining whether the data returned from an action is a success.
+    ///
+    /// This allows chaining for action data which does not implement [`ActionOutcome`].
+    ///
+    /// # Example
+    /// ```rust
+    /// use cmdtree::*;
+    /// let mut cmder = Builder::new("base")
+    ///     .add_action("count", "", |_wtr, args| args.len())
+    ///     .into_commander().unwrap();
+    ///
+    /// let results = cmder
+    ///     .try_parse_chain_with("count && count a || count a b", false, &mut std::io::sink(), |n| *n > 0)
+    ///     .unwrap();
+    /// assert_eq!(results, vec![LineResult::Action(0), LineResult::Action(2)]);
+    /// ```
+    pub fn try_parse_chain_with<W, F>(
+        &mut self,
+        line: &str,
+        colourise: bool,
+        writer: &mut W,
+        is_success: F,
+    ) -> io::Result<Vec<LineResult<R>>>
+    where
+        W: Write,
+        F: Fn(&R) -> bool,
+    {
+        let segments = match tokenize_chain(line) {
+            Ok(segments) => segments,
+            Err(quote) => return unterminated_quote(quote, colourise, writer).map(|r| vec![r]),
+        };
+
+        let mut results = Vec::with_capacity(segments.len());
+        let mut success = true;
+
+        for (chain, words, source) in segments {
+            let run = match chain {
+                Chain::Always => true,
+                Chain::And => success,
+                Chain::Or => !success,
             };
-        }
 
-        LineResult::Class // default
-    }
-}
+            if !run || words.is_empty() {
+                continue;
+            }
 
-fn parse_word<'a, R>(subclass: &'a SubClass<R>, word: &str) -> WordResult<'a, R> {
-    let lwr = word.to_lowercase();
-    match lwr.as_str() {
-        "help" => WordResult::Help(subclass),
-        "cancel" | "c" => WordResult::Cancel,
-        "exit" => WordResult::Exit,
-        word => {
-            if let Some(c) = subclass.classes.iter().find(|c| c.name.as_str() == word) {
-                WordResult::Class(c)
-            } else if let Some(a) = subclass.actions.iter().find(|a| a.name.as_str() == word) {
-                WordResult::Action(a)
-            } else {
-                WordResult::Unrecognized
+            let r = self.parse_words(source, &words, colourise, writer)?;
+            success = r.succeeded(&is_success);
+            let exit = matches!(r, LineResult::Exit);
+            results.push(r);
+
+            if exit {
+                break;
             }
         }
+
+        Ok(results)
     }
-}
 
-fn write_help_coloured<W: Write, R>(class: &SubClass<R>, writer: &mut W) -> io::Result<()> {
-    writeln!(
-        writer,
-        "{} -- prints the help messages",
-        "help".bright_yellow()
-    )?;
-    writeln!(
-        writer,
-        "{} | {} -- returns to the root class",
-        "cancel".bright_yellow(),
-        "c".bright_yellow()
-    )?;
-    writeln!(
-        writer,
-        "{} -- sends the exit signal to end the interactive loop",
-        "exit".bright_yellow()
-    )?;
-    if !class.classes.is_empty() {
-        writeln!(writer, "{}", "Classes:".bright_purple())?;
-        for class in class.classes.iter() {
-            writeln!(writer, "\t{} -- {}", class.name.bright_yellow(), class.help)?;
-        }
+    /// Run a single command from a process argument vector, returning the process exit code.
+    ///
+    /// This allows the same tree to back a one-shot command line tool, `mytool net status --json`,
+    /// as well as the interactive loop. The first item is the program name, as with `std::env::args()`, and is skipped.
+    /// The remaining items are resolved against the tree as already split words, classes acting as subcommands
+    /// and the first action taking the rest of the items as its arguments. Output is written to stdout without colour.
+    ///
+    /// If `--help` appears among the classes or before the action, the help of the class or action the words
+    /// resolve to is printed instead. After the action, `--help` is only taken as help before any `--`,
+    /// and if the action does not declare a `help` flag or option of its own.
+    /// Stopping at a class prints the help of that class.
+    /// The exit code is determined by [`LineResult::exit_code`], or `1` if writing to stdout fails.
+    ///
+    /// # Example
+    /// ```rust,no_run
+    /// use cmdtree::*;
+    /// let mut cmder = Builder::new("mytool")
+    ///     .begin_class("net", "network commands")
+    ///     .add_action("status", "print the network status", |_wtr, _args| true)
+    ///     .into_commander().unwrap();
+    ///
+    /// std::process::exit(cmder.run_args(std::env::args()));
+    /// ```
+    pub fn run_args<I>(&mut self, args: I) -> i32
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+        R: ActionOutcome,
+    {
+        let stdout = io::stdout();
+        let mut stdout = stdout.lock();
+        self.run_args_with(args, false, &mut stdout).unwrap_or(1)
     }
 
-    if !class.actions.is_empty() {
-        writeln!(writer, "{}", "Actions:".bright_purple())?;
-        for action in class.actions.iter() {
-            writeln!(
-                writer,
-                "\t{} -- {}",
-                action.name.bright_yellow(),
-                action.help
-            )?;
+    /// Run a single command from a process argument vector, writing to `writer`.
+    /// See [`run_args`](Commander::run_args).
+    ///
+    /// # Example
+    /// ```rust
+    /// use cmdtree::*;
+    /// let mut cmder = Builder::new("mytool")
+    ///     .begin_class("net", "network commands")
+    ///     .add_action("status", "print the network status", |_wtr, args| args.is_empty())
+    ///     .into_commander().unwrap();
+    ///
+    /// let mut out = Vec::new();
+    /// assert_eq!(cmder.run_args_with(vec!["mytool", "net", "status"], false, &mut out).unwrap(), 0);
+    /// assert_eq!(cmder.run_args_with(vec!["mytool", "net", "status", "x"], false, &mut out).unwrap(), 1);
+    /// assert_eq!(cmder.run_args_with(vec!["mytool", "net"], false, &mut out).unwrap(), 2);
+    /// ```
+    pub fn run_args_with<I, W>(
+        &mut self,
+        args: I,
+        colourise: bool,
+        writer: &mut W,
+    ) -> io::Result<i32>
+    where
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+        W: Write,
+        R: ActionOutcome,
+    {
+        let mut words: Vec<String> = args
+            .into_iter()
+            .skip(1)
+            .map(|x| x.as_ref().to_string())
+            .collect();
+
+        if self.args_help_requested(&words) {
+            let opts_end = words.iter().position(|w| w == "--").unwrap_or(words.len());
+            words.truncate(opts_end);
+            words.retain(|w| w != "--help");
+            return self.write_args_help(&words, colourise, writer).map(|_| 0);
         }
-    }
 
-    Ok(())
-}
+        let line = words.join(" ");
+        let start = self.position();
+        // a class only prints its help, so the history is left as it was
+        let history = (
+            self.back_history.clone(),
+            self.forward_history.clone(),
+            self.dir_stack.clone(),
+        );
 
-fn write_help<W: Write, R>(class: &SubClass<R>, writer: &mut W) -> io::Result<()> {
-    writeln!(writer, "help -- prints the help messages",)?;
-    writeln!(writer, "cancel | c -- returns to the root class",)?;
-    writeln!(
-        writer,
-        "exit -- sends the exit signal to end the interactive loop",
-    )?;
-    if !class.classes.is_empty() {
-        writeln!(writer, "Classes:")?;
-        for class in class.classes.iter() {
-            writeln!(writer, "\t{} -- {}", class.name, class.help)?;
+        let r = self.parse_words(&line, &words, colourise, writer)?;
+        if let LineResult::Class = r {
+            let written = self.write_class_help(&self.current, colourise, writer);
+            self.set_position(start);
+            (self.back_history, self.forward_history, self.dir_stack) = history;
+            written?;
         }
+
+        Ok(r.exit_code())
     }
 
-    if !class.actions.is_empty() {
-        writeln!(writer, "Actions:")?;
-        for action in class.actions.iter() {
-            writeln!(writer, "\t{} -- {}", action.name, action.help)?;
+    /// Whether `words` contain a `--help` asking for the help of the class or action they resolve to.
+    fn args_help_requested(&self, words: &[String]) -> bool {
+        let before_terminator = |words: &[String]| {
+            words
+                .iter()
+                .take_while(|w| *w != "--")
+                .any(|w| w == "--help")
+        };
+        let mut class = Arc::clone(&self.current);
+
+        for (i, word) in words.iter().enumerate() {
+            if word == "--help" {
+                return true;
+            }
+            let next = match parse_word(&class, word, &self.builtins, self.abbreviations) {
+                WordResult::Class(c) => Arc::clone(c),
+                WordResult::Action(a) => {
+                    let own_help = a
+                        .args
+                        .as_ref()
+                        .is_some_and(|spec| spec.flags().iter().any(|f| f.long == "help"));
+                    return !own_help && before_terminator(&words[i + 1..]);
+                }
+                _ => return before_terminator(&words[i..]),
+            };
+            class = next;
         }
+
+        false
     }
 
-    Ok(())
-}
+    /// Writes the help of the class or action that `words` resolve to.
+    fn write_args_help<W: Write>(
+        &self,
+        words: &[String],
+        colourise: bool,
+        writer: &mut W,
+    ) -> io::Result<()> {
+        let mut class = Arc::clone(&self.current);
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+        for word in words {
+            let next = match parse_word(&class, word, &self.builtins, self.abbreviations) {
+                WordResult::Class(c) => Arc::clone(c),
+                WordResult::Action(a) => {
+                    let name = if colourise {
+                        names_coloured(a.names())
+                    } else {
+                        a.names().collect::<Vec<_>>().join(" | ")
+                    };
+                    return writeln!(writer, "{}{} -- {}", name, arg_usage(a), a.help);
+                }
+                _ => break,
+            };
+            class = next;
+        }
 
-    #[test]
-    fn parse_line_test() {
-        let mut cmder = Builder::default_config("test")
-            .begin_class("class1", "class1 help")
-            .begin_class("class1-class1", "adsf")
-            .add_action("action1", "adf", |_, _| ())
-            .end_class()
-            .begin_class("class1-class2", "adsf")
-            .add_action("action2", "adsf", |_, _| ())
-            .end_class()
-            .end_class()
-            .begin_class("class2", "asdf")
-            .end_class()
-            .add_action("test-args", "", |_wtr, args| {
-                assert_eq!(&args, &["one", "two", "three"])
-            })
-            .into_commander()
-            .unwrap();
+        self.write_class_help(&class, colourise, writer)
+    }
 
-        let w = &mut std::io::sink();
+    fn write_class_help<W: Write>(
+        &self,
+        class: &SubClass<R, S>,
+        colourise: bool,
+        writer: &mut W,
+    ) -> io::Result<()> {
+        if colourise {
+            write_help_coloured(class, &self.builtins, writer)
+        } else {
+            write_help(class, &self.builtins, writer)
+        }
+    }
 
-        assert_eq!(cmder.parse_line("adsf", true, w), LineResult::Unrecognized); // unrecognised branch
-        assert_eq!(cmder.current, cmder.root);
-        assert_eq!(cmder.parse_line("adsf", false, w), LineResult::Unrecognized); // unrecognised branch
-        assert_eq!(cmder.current, cmder.root);
+    /// Runs the `alias` or `unalias` builtin if it is the first word, returning `None` if neither is.
+    fn alias_command<W: Write>(
+        &mut self,
+        words: &[String],
+        colourise: bool,
+        writer: &mut W,
+    ) -> Option<io::Result<LineResult<R>>> {
+        let command = self.builtins.find(words.first()?)?;
+        let name = words.get(1).map(|x| x.to_lowercase());
+        let usage = |args: &str| format!("usage: {} {}", words[0].to_lowercase(), args);
 
-        assert_eq!(cmder.parse_line("class1", true, w), LineResult::Class);
-        assert_ne!(cmder.current, cmder.root);
-        assert_eq!(cmder.current.name, "class1");
+        let written = match (command, name) {
+            (Builtin::Alias, None) => self.save_runtime_aliases(writer),
+            (Builtin::Alias, Some(name)) => match alias_definition(&words[1..]) {
+                Some((name, expansion)) => match self.set_runtime_alias(&name, &expansion) {
+                    Ok(_) => Ok(()),
+                    Err(BuildError::NameIsBuiltin(_)) => write_error(
+                        writer,
+                        &format!("'{}' is the name of a builtin", name),
+                        colourise,
+                    ),
+                    Err(_) => write_error(
+                        writer,
+                        &format!("'{}' is the name of a class or action", name),
+                        colourise,
+                    ),
+                },
+                None if words.len() > 2 => {
+                    write_error(writer, &usage("<name> = <command...>"), colourise)
+                }
+                None => match self.runtime_aliases.get(&name) {
+                    Some(expansion) => writeln!(writer, "{} = {}", name, expansion),
+                    None => write_error(writer, &format!("no alias '{}'", name), colourise),
+                },
+            },
+            (Builtin::Unalias, Some(name)) if words.len() == 2 => {
+                match self.remove_runtime_alias(&name) {
+                    Some(_) => Ok(()),
+                    None => write_error(writer, &format!("no alias '{}'", name), colourise),
+                }
+            }
+            (Builtin::Unalias, _) => write_error(writer, &usage("<name>"), colourise),
+            _ => return None,
+        };
 
-        // should be able to action here
-        assert_eq!(
-            cmder.parse_line("class1-class1 action1", true, w),
-            LineResult::Action(())
-        );
-        assert_eq!(cmder.current.name, "class1");
-        assert_eq!(
-            cmder.parse_line("class1-class2 action2", true, w),
-            LineResult::Action(())
-        );
-        assert_eq!(cmder.current.name, "class1");
+        Some(written.map(|_| LineResult::Alias))
+    }
 
-        // get back to root
-        assert_eq!(cmder.parse_line("cancel", true, w), LineResult::Cancel);
-        assert_eq!(cmder.current.name, "test");
+    /// Runs a history builtin if it is the first word, returning `None` if none is.
+    fn history_command<W: Write>(
+        &mut self,
+        line: &str,
+        words: &[String],
+        expand: bool,
+        colourise: bool,
+        writer: &mut W,
+    ) -> Option<io::Result<LineResult<R>>> {
+        let builtin = self.builtins.find(words.first()?)?;
+        let rest = &words[1..];
+        let (moved, msg) = match builtin {
+            Builtin::Back => (self.back(), "no previous class to go back to"),
+            Builtin::Forward => (self.forward(), "no class to go forward to"),
+            Builtin::Pop => (self.pop_dir(), "the class stack is empty"),
+            Builtin::Push if rest.is_empty() => (self.swap_dir(), "the class stack is empty"),
+            Builtin::Push => {
+                let start = self.position();
+                let r = self.expand_words(line, rest, expand, colourise, writer);
+                if let Ok(LineResult::Class) = r {
+                    self.push_dir_stack(start);
+                }
+                return Some(r);
+            }
+            _ => return None,
+        };
 
-        // test args
+        Some(if !moved {
+            write_error(writer, msg, colourise).map(|_| LineResult::NoHistory)
+        } else if rest.is_empty() {
+            Ok(LineResult::Class)
+        } else {
+            self.expand_words(line, rest, expand, colourise, writer)
+        })
+    }
+
+    fn parse_words<W: Write>(
+        &mut self,
+        line: &str,
+        words: &[String],
+        colourise: bool,
+        writer: &mut W,
+    ) -> io::Result<LineResult<R>> {
+        self.expand_words(line, words, true, colourise, writer)
+    }
+
+    /// Expands a runtime alias in the first word if `expand` is set, then runs the words.
+    ///
+    /// The words of an expansion, and the words run by a builtin following it, are not expanded again.
+    fn expand_words<W: Write>(
+        &mut self,
+        line: &str,
+        words: &[String],
+        expand: bool,
+        colourise: bool,
+        writer: &mut W,
+    ) -> io::Result<LineResult<R>> {
+        let expanded;
+        let (words, expand) =
+            match expand_alias(words, expand, &self.builtins, &self.runtime_aliases) {
+                Some(Ok(expansion)) => {
+                    expanded = expansion;
+                    (&expanded[..], false)
+                }
+                Some(Err(quote)) => return unterminated_quote(quote, colourise, writer),
+                None => (words, expand),
+            };
+
+        if let Some(r) = self.alias_command(words, colourise, writer) {
+            return r;
+        }
+        if let Some(r) = self.history_command(line, words, expand, colourise, writer) {
+            return r;
+        }
+
+        let start = self.position();
+        let mut idx = 0;
+
+        while let Some(word) = words.get(idx) {
+            idx += 1;
+            let steps = path_steps(&self.current, &self.root.name, &self.builtins, word);
+            let last = steps.len() - 1;
+
+            for (i, step) in steps.into_iter().enumerate() {
+                let segment = match step {
+                    PathStep::Root => {
+                        self.goto_root();
+                        continue;
+                    }
+                    PathStep::Word(segment) => segment,
+                };
+
+                let current = Arc::clone(&self.current);
+                match parse_word(&current, segment, &self.builtins, self.abbreviations) {
+                    WordResult::Help(sc) => {
+                        let written = self.write_class_help(sc, colourise, writer);
+                        self.set_position(start);
+                        return written.map(|_| LineResult::Help);
+                    }
+                    WordResult::Cancel => {
+                        self.goto_root();
+                        self.record_history(start);
+                        return Ok(LineResult::Cancel);
+                    }
+                    WordResult::Exit => {
+                        return Ok(LineResult::Exit);
+                    }
+                    WordResult::Up => self.leave_class(),
+                    WordResult::Root => self.goto_root(),
+                    WordResult::Class(sc) => self.enter_class(Arc::clone(sc)),
+                    // an action ends a path, anything following it is unrecognized
+                    WordResult::Action(_) if i != last => {
+                        return self.unrecognized(word, start, colourise, writer);
+                    }
+                    WordResult::Action(a) => {
+                        let slice: Vec<&str> = words[idx..].iter().map(String::as_str).collect();
+                        let qualified_path = format!(
+                            "{}..{}",
+                            self.path[self.root.name.len()..].trim_start_matches(PATH_SEP),
+                            a.name
+                        );
+                        let r = a.call(&mut ActionContext {
+                            writer,
+                            args: &slice,
+                            state: &mut self.state.write().unwrap_or_else(PoisonError::into_inner),
+                            root_name: &self.root.name,
+                            path: &self.path,
+                            qualified_path,
+                            line,
+                            colourise,
+                            parsed: None,
+                        });
+                        let (r, written) = match r {
+                            Ok(r) => (LineResult::Action(r), Ok(())),
+                            Err(CallError::Args(e)) => {
+                                let written = write_error(
+                                    writer,
+                                    &format!("{}\nusage: {}{}", e, a.name, arg_usage(a)),
+                                    colourise,
+                                );
+                                (LineResult::InvalidArgs(e), written)
+                            }
+                            Err(CallError::Action(e)) => {
+                                let written =
+                                    write_action_error(writer, &e, self.error_chain, colourise);
+                                (LineResult::ActionError(e), written)
+                            }
+                        };
+                        self.set_position(start);
+                        return written.map(|_| r);
+                    }
+                    WordResult::Ambiguous(candidates) => {
+                        let written = write_error(
+                            writer,
+                            &format!(
+                                "'{}' is ambiguous, could be: {}",
+                                segment,
+                                candidates.join(", ")
+                            ),
+                            colourise,
+                        );
+                        self.set_position(start);
+                        return written.map(|_| LineResult::Ambiguous {
+                            word: segment.to_string(),
+                            candidates,
+                        });
+                    }
+                    WordResult::Unrecognized => {
+                        return self.unrecognized(segment, start, colourise, writer);
+                    }
+                }
+            }
+        }
+
+        self.record_history(start);
+        Ok(LineResult::Class) // default
+    }
+
+    /// Writes the unrecognized word error, with suggestions from the current class,
+    /// and returns to the `start` position.
+    fn unrecognized<W: Write>(
+        &mut self,
+        word: &str,
+        start: Position<R, S>,
+        colourise: bool,
+        writer: &mut W,
+    ) -> io::Result<LineResult<R>> {
+        let suggestions = suggestions(&self.current, word, &self.builtins);
+        let mut msg = format!(
+            "'{}' does not match any keywords, classes, or actions",
+            word
+        );
+        if !suggestions.is_empty() {
+            msg.push_str(&format!("\ndid you mean: {}?", suggestions.join(", ")));
+        }
+        let written = write_error(writer, &msg, colourise);
+        self.set_position(start);
+        written.map(|_| LineResult::Unrecognized {
+            word: word.to_string(),
+            suggestions,
+        })
+    }
+
+    /// A resolver of words from the current class.
+    pub(crate) fn resolver(&self) -> Resolver<R, S> {
+        Resolver {
+            root: Arc::clone(&self.root),
+            current: Arc::clone(&self.current),
+            parents: self.parents.clone(),
+            abbreviations: self.abbreviations,
+            builtins: self.builtins.clone(),
+            runtime_aliases: self.runtime_aliases.clone(),
+        }
+    }
+}
+
+/// A snapshot of the position and configuration of a `Commander`,
+/// resolving words as `parse_line` does without running anything.
+pub(crate) struct Resolver<R, S> {
+    root: Arc<SubClass<R, S>>,
+    current: Arc<SubClass<R, S>>,
+    parents: Vec<Arc<SubClass<R, S>>>,
+    abbreviations: bool,
+    builtins: Builtins,
+    runtime_aliases: BTreeMap<String, String>,
+}
+
+impl<R, S> Resolver<R, S> {
+    /// Resolves the words of a line, expanding a runtime alias in the first word.
+    pub(crate) fn resolve(&self, words: &[String]) -> Resolved<R, S> {
+        self.resolve_words(words, true)
+    }
+
+    fn resolve_words(&self, words: &[String], expand: bool) -> Resolved<R, S> {
+        let expanded;
+        let (words, expand) =
+            match expand_alias(words, expand, &self.builtins, &self.runtime_aliases) {
+                Some(Ok(expansion)) => {
+                    expanded = expansion;
+                    (&expanded[..], false)
+                }
+                Some(Err(_)) => return Resolved::Other,
+                None => (words, expand),
+            };
+
+        // the alias builtins take no path, and the history builtins other than push
+        // move to a class which is not known here
+        match words.first().and_then(|w| self.builtins.find(w)) {
+            Some(Builtin::Push) => return self.resolve_words(&words[1..], expand),
+            Some(Builtin::Alias)
+            | Some(Builtin::Unalias)
+            | Some(Builtin::Back)
+            | Some(Builtin::Forward)
+            | Some(Builtin::Pop) => return Resolved::Other,
+            _ => (),
+        }
+
+        let mut class = Arc::clone(&self.current);
+        let mut parents = self.parents.clone();
+
+        for (idx, word) in words.iter().enumerate() {
+            let steps = path_steps(&class, &self.root.name, &self.builtins, word);
+            let last = steps.len() - 1;
+
+            for (i, step) in steps.into_iter().enumerate() {
+                let segment = match step {
+                    PathStep::Root => {
+                        class = Arc::clone(&self.root);
+                        parents.clear();
+                        continue;
+                    }
+                    PathStep::Word(segment) => segment,
+                };
+
+                match parse_word(&class, segment, &self.builtins, self.abbreviations) {
+                    WordResult::Class(sc) => {
+                        let sc = Arc::clone(sc);
+                        parents.push(std::mem::replace(&mut class, sc));
+                    }
+                    WordResult::Action(a) if i == last => {
+                        let index = class
+                            .actions
+                            .iter()
+                            .position(|x| std::ptr::eq(x, a))
+                            .expect("action is in the class");
+                        let args = words[idx + 1..].to_vec();
+                        return Resolved::Action(Arc::clone(&class), index, args);
+                    }
+                    WordResult::Up => {
+                        if let Some(parent) = parents.pop() {
+                            class = parent;
+                        }
+                    }
+                    WordResult::Root => {
+                        class = Arc::clone(&self.root);
+                        parents.clear();
+                    }
+                    _ => return Resolved::Other,
+                }
+            }
+        }
+
+        Resolved::Class(class)
+    }
+}
+
+/// Splits a word into the steps of a path.
+///
+/// A word starting with the root builtin, such as `/one.two`, is addressed from the root.
+/// A word containing `.` which is not a name in the `current` class is split into its segments,
+/// and is addressed from the root if the first segment is the root name.
+fn path_steps<'w, R, S>(
+    current: &SubClass<R, S>,
+    root_name: &str,
+    builtins: &Builtins,
+    word: &'w str,
+) -> Vec<PathStep<'w>> {
+    let single = vec![PathStep::Word(word)];
+    if builtins.find(word).is_some() {
+        return single;
+    }
+
+    let (mut steps, rest) = match builtins
+        .names(Builtin::Root)
+        .iter()
+        .find(|token| word.starts_with(token.as_str()))
+    {
+        Some(token) => (vec![PathStep::Root], &word[token.len()..]),
+        None => {
+            let lwr = word.to_lowercase();
+            let is_name = current.classes.iter().any(|c| c.names().any(|n| n == lwr))
+                || current.actions.iter().any(|a| a.names().any(|n| n == lwr));
+            if is_name || !word.contains(PATH_SEP) {
+                return single;
+            }
+            (Vec::new(), word)
+        }
+    };
+
+    let segments: Vec<&str> = rest.split(PATH_SEP).collect();
+    if segments.iter().any(|s| s.is_empty()) {
+        return single;
+    }
+
+    let mut segments = segments.into_iter();
+    if steps.is_empty() {
+        let first = segments.next().unwrap_or_default();
+        let is_child = current
+            .classes
+            .iter()
+            .any(|c| c.names().any(|n| n == first.to_lowercase()));
+        if !is_child && first.to_lowercase() == root_name {
+            steps.push(PathStep::Root);
+        } else {
+            steps.push(PathStep::Word(first));
+        }
+    }
+    steps.extend(segments.map(PathStep::Word));
+    steps
+}
+
+/// Expands a runtime alias in the first word if `expand` is set and the word is not a builtin,
+/// followed by the remaining words.
+/// Returns `None` if there is no alias, or the opening quote character if the expansion leaves a quote open.
+fn expand_alias(
+    words: &[String],
+    expand: bool,
+    builtins: &Builtins,
+    runtime_aliases: &BTreeMap<String, String>,
+) -> Option<Result<Vec<String>, char>> {
+    let expansion = words
+        .first()
+        .filter(|w| expand && builtins.find(w).is_none())
+        .and_then(|w| runtime_aliases.get(&w.to_lowercase()))?;
+    Some(tokenize(expansion).map(|mut expansion| {
+        expansion.extend_from_slice(&words[1..]);
+        expansion
+    }))
+}
+
+fn unterminated_quote<R, W: Write>(
+    quote: char,
+    colourise: bool,
+    writer: &mut W,
+) -> io::Result<LineResult<R>> {
+    write_error(
+        writer,
+        &format!("unterminated quote, expecting a closing {}", quote),
+        colourise,
+    )?;
+    Ok(LineResult::UnterminatedQuote(quote))
+}
+
+/// Splits the words following `alias` into a name and an expansion,
+/// accepting `name = command...`, `name= command...`, `name =command...` and `name=command...`.
+/// Returns `None` if there is no `=` or either side is empty.
+fn alias_definition(words: &[String]) -> Option<(String, String)> {
+    let (name, first) = match words[0].find('=') {
+        Some(idx) => (&words[0][..idx], &words[0][idx + 1..]),
+        None => match words.get(1) {
+            Some(w) if w.starts_with('=') => (words[0].as_str(), &w[1..]),
+            _ => return None,
+        },
+    };
+    let rest = if words[0].contains('=') {
+        &words[1..]
+    } else {
+        &words[2..]
+    };
+
+    let expansion: Vec<String> = std::iter::once(first)
+        .filter(|x| !x.is_empty())
+        .chain(rest.iter().map(String::as_str))
+        .map(quote_word)
+        .collect();
+
+    if name.is_empty() || expansion.is_empty() {
+        None
+    } else {
+        Some((name.to_string(), expansion.join(" ")))
+    }
+}
+
+/// Quotes a word so it tokenizes back to itself.
+fn quote_word(word: &str) -> String {
+    let plain = !word.is_empty()
+        && !word
+            .chars()
+            .any(|c| c.is_whitespace() || "'\"\\;&|".contains(c));
+    if plain {
+        word.to_string()
+    } else {
+        format!("'{}'", word.replace('\'', "'\\''"))
+    }
+}
+
+/// Splits a line into words.
+/// See [`lex`] for the rules.
+fn tokenize(line: &str) -> Result<Vec<String>, char> {
+    lex(line, false).map(|tokens| {
+        tokens
+            .into_iter()
+            .filter_map(|t| match t {
+                Token::Word(w) => Some(w),
+                Token::Op(..) => None,
+            })
+            .collect()
+    })
+}
+
+/// The operator joining a segment to the previous one, its words and its source text.
+type Segment<'a> = (Chain, Vec<String>, &'a str);
+
+/// Splits a line into segments of words, each paired with the operator joining it to the previous segment
+/// and the trimmed source text of the segment.
+/// The first segment is always paired with `Chain::Always`.
+fn tokenize_chain(line: &str) -> Result<Vec<Segment<'_>>, char> {
+    let mut segments = vec![(Chain::Always, Vec::new(), line)];
+    let mut start = 0;
+
+    for token in lex(line, true)? {
+        let last = segments.last_mut().expect("always at least one segment");
+        match token {
+            Token::Word(w) => last.1.push(w),
+            Token::Op(op, range) => {
+                last.2 = line[start..range.start].trim();
+                start = range.end;
+                segments.push((op, Vec::new(), &line[start..]));
+            }
+        }
+    }
+
+    let last = segments.last_mut().expect("always at least one segment");
+    last.2 = last.2.trim();
+
+    Ok(segments)
+}
+
+/// Splits the last segment of a chained line into its words before the word being completed,
+/// and the (possibly empty) word being completed.
+///
+/// A quote left open is closed, so a quoted word can be completed.
+/// Returns `None` if the line cannot be tokenized.
+pub(crate) fn split_completed_word(line: &str) -> Option<(Vec<String>, String)> {
+    let words = |line: &str| tokenize_chain(line).ok()?.pop().map(|x| x.1);
+    let line = match tokenize_chain(line) {
+        Ok(_) => line.to_string(),
+        Err(quote) => format!("{}{}", line, quote),
+    };
+
+    let mut preceding = words(&line)?;
+    // a character appended to a word in progress joins it, otherwise it starts a new word
+    let word = if words(&format!("{}x", line))?.len() > preceding.len() {
+        String::new()
+    } else {
+        preceding.pop().unwrap_or_default()
+    };
+
+    Some((preceding, word))
+}
+
+/// Splits a line into tokens.
+///
+/// Runs of whitespace separate words. Single quotes preserve everything literally,
+/// double quotes preserve everything except `\"` and `\\` escapes, and outside of quotes
+/// a backslash escapes the next character.
+/// If `chaining` is set, unquoted `;`, `&&` and `||` are returned as operators.
+/// If a quote is left open the opening quote character is returned as the error.
+fn lex(line: &str, chaining: bool) -> Result<Vec<Token>, char> {
+    let mut tokens = Vec::new();
+    let mut word = String::new();
+    let mut in_word = false; // distinguishes an empty quoted word from no word
+    let mut chars = line.char_indices().peekable();
+
+    while let Some((i, ch)) = chars.next() {
+        match ch {
+            '\'' | '"' => {
+                in_word = true;
+                loop {
+                    match chars.next().map(|x| x.1) {
+                        Some(c) if c == ch => break,
+                        Some('\\') if ch == '"' => match chars.next().map(|x| x.1) {
+                            Some(c) if c == '"' || c == '\\' => word.push(c),
+                            Some(c) => {
+                                word.push('\\');
+                                word.push(c);
+                            }
+                            None => return Err(ch),
+                        },
+                        Some(c) => word.push(c),
+                        None => return Err(ch),
+                    }
+                }
+            }
+            '\\' => {
+                in_word = true;
+                word.push(chars.next().map_or('\\', |x| x.1));
+            }
+            c if c.is_whitespace() => {
+                if in_word {
+                    tokens.push(Token::Word(std::mem::take(&mut word)));
+                    in_word = false;
+                }
+            }
+            ';' | '&' | '|' if chaining && (ch == ';' || chars.peek().map(|x| x.1) == Some(ch)) => {
+                if in_word {
+                    tokens.push(Token::Word(std::mem::take(&mut word)));
+                    in_word = false;
+                }
+                let op = match ch {
+                    ';' => Chain::Always,
+                    '&' => Chain::And,
+                    _ => Chain::Or,
+                };
+                if op != Chain::Always {
+                    chars.next(); // consume the second character
+                }
+                let end = i + if op == Chain::Always { 1 } else { 2 };
+                tokens.push(Token::Op(op, i..end));
+            }
+            c => {
+                in_word = true;
+                word.push(c);
+            }
+        }
+    }
+
+    if in_word {
+        tokens.push(Token::Word(word));
+    }
+
+    Ok(tokens)
+}
+
+/// Resolves a word against the builtins, classes and actions of `subclass`.
+///
+/// If `abbreviate` is set and no name matches exactly, a word which is a prefix of
+/// exactly one class or action name resolves to it.
+fn parse_word<'a, R, S>(
+    subclass: &'a SubClass<R, S>,
+    word: &str,
+    builtins: &Builtins,
+    abbreviate: bool,
+) -> WordResult<'a, R, S> {
+    let lwr = word.to_lowercase();
+    match builtins.find(&lwr) {
+        Some(Builtin::Help) => WordResult::Help(subclass),
+        Some(Builtin::Cancel) => WordResult::Cancel,
+        Some(Builtin::Exit) => WordResult::Exit,
+        Some(Builtin::Up) => WordResult::Up,
+        Some(Builtin::Root) => WordResult::Root,
+        // alias, unalias and the history builtins are only recognised as the first word of a line
+        Some(Builtin::Alias)
+        | Some(Builtin::Unalias)
+        | Some(Builtin::Back)
+        | Some(Builtin::Forward)
+        | Some(Builtin::Push)
+        | Some(Builtin::Pop)
+        | None => {
+            let word = lwr.as_str();
+            if let Some(c) = subclass
+                .classes
+                .iter()
+                .find(|c| c.names().any(|n| n == word))
+            {
+                WordResult::Class(c)
+            } else if let Some(a) = subclass
+                .actions
+                .iter()
+                .find(|a| a.names().any(|n| n == word))
+            {
+                WordResult::Action(a)
+            } else if abbreviate {
+                parse_abbreviation(subclass, word)
+            } else {
+                WordResult::Unrecognized
+            }
+        }
+    }
+}
+
+fn parse_abbreviation<'a, R, S>(subclass: &'a SubClass<R, S>, word: &str) -> WordResult<'a, R, S> {
+    let classes: Vec<_> = subclass
+        .classes
+        .iter()
+        .filter(|c| c.names().any(|n| n.starts_with(word)))
+        .collect();
+    let actions: Vec<_> = subclass
+        .actions
+        .iter()
+        .filter(|a| a.names().any(|n| n.starts_with(word)))
+        .collect();
+
+    match (classes.as_slice(), actions.as_slice()) {
+        ([], []) => WordResult::Unrecognized,
+        ([c], []) => WordResult::Class(c),
+        ([], [a]) => WordResult::Action(a),
+        _ => WordResult::Ambiguous(
+            classes
+                .iter()
+                .map(|c| c.name.clone())
+                .chain(actions.iter().map(|a| a.name.clone()))
+                .collect(),
+        ),
+    }
+}
+
+/// The names of the classes, actions and builtins of `subclass` which are close to `word`, closest first.
+///
+/// A name is close if `word` is a prefix of it, or it is within a small edit distance relative to the length of `word`.
+fn suggestions<R, S>(subclass: &SubClass<R, S>, word: &str, builtins: &Builtins) -> Vec<String> {
+    let word = word.to_lowercase();
+    let max_distance = std::cmp::max(word.chars().count() / 3, 1);
+
+    let mut matches: Vec<(usize, &str)> = builtins
+        .iter()
+        // the navigation tokens are too short to be useful suggestions
+        .filter(|(b, _)| !matches!(b, Builtin::Up | Builtin::Root))
+        .map(|(_, names)| names[0].as_str())
+        .chain(subclass.classes.iter().flat_map(|c| c.names()))
+        .chain(subclass.actions.iter().flat_map(|a| a.names()))
+        .filter_map(|name| {
+            let distance = edit_distance(&word, name);
+            if distance <= max_distance || name.starts_with(word.as_str()) {
+                Some((distance, name))
+            } else {
+                None
+            }
+        })
+        .collect();
+
+    matches.sort();
+    matches
+        .into_iter()
+        .map(|(_, name)| name.to_string())
+        .collect()
+}
+
+/// The edit distance between two strings, counting insertions, deletions, substitutions and
+/// transpositions of adjacent characters as one edit each (optimal string alignment).
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut d = vec![vec![0; b.len() + 1]; a.len() + 1];
+
+    for (i, row) in d.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, x) in d[0].iter_mut().enumerate() {
+        *x = j;
+    }
+
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = if a[i - 1] == b[j - 1] { 0 } else { 1 };
+            d[i][j] = (d[i - 1][j] + 1)
+                .min(d[i][j - 1] + 1)
+                .min(d[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                d[i][j] = d[i][j].min(d[i - 2][j - 2] + 1);
+            }
+        }
+    }
+
+    d[a.len()][b.len()]
+}
+
+/// The argument usage of an action, prefixed with a space, or empty if the action has no arguments.
+fn arg_usage<R, S>(action: &Action<R, S>) -> String {
+    match &action.args {
+        Some(spec) if !spec.is_empty() => format!(" {}", spec.usage()),
+        _ => String::new(),
+    }
+}
+
+fn write_error<W: Write>(writer: &mut W, msg: &str, colourise: bool) -> io::Result<()> {
+    let mut s = msg.bright_red();
+
+    if !colourise {
+        s = s.white();
+    }
+
+    writeln!(writer, "{}", s)
+}
+
+fn write_action_error<W: Write>(
+    writer: &mut W,
+    error: &ActionError,
+    chain: bool,
+    colourise: bool,
+) -> io::Result<()> {
+    write_error(writer, &format!("error: {}", error), colourise)?;
+
+    if chain {
+        for source in error.sources() {
+            write_error(writer, &format!("  caused by: {}", source), colourise)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// The names joined by ` | `, each coloured.
+fn names_coloured<'a, I: Iterator<Item = &'a str>>(names: I) -> String {
+    names
+        .map(|n| n.bright_yellow().to_string())
+        .collect::<Vec<_>>()
+        .join(" | ")
+}
+
+fn write_help_coloured<W: Write, R, S>(
+    class: &SubClass<R, S>,
+    builtins: &Builtins,
+    writer: &mut W,
+) -> io::Result<()> {
+    for (builtin, names) in builtins.iter() {
+        writeln!(
+            writer,
+            "{} -- {}",
+            names_coloured(names.iter().map(String::as_str)),
+            builtin.help()
+        )?;
+    }
+    if !class.classes.is_empty() {
+        writeln!(writer, "{}", "Classes:".bright_purple())?;
+        for class in class.classes.iter() {
+            writeln!(
+                writer,
+                "\t{} -- {}",
+                names_coloured(class.names()),
+                class.help
+            )?;
+        }
+    }
+
+    if !class.actions.is_empty() {
+        writeln!(writer, "{}", "Actions:".bright_purple())?;
+        for action in class.actions.iter() {
+            writeln!(
+                writer,
+                "\t{}{} -- {}",
+                names_coloured(action.names()),
+                arg_usage(action),
+                action.help
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+fn write_help<W: Write, R, S>(
+    class: &SubClass<R, S>,
+    builtins: &Builtins,
+    writer: &mut W,
+) -> io::Result<()> {
+    for (builtin, names) in builtins.iter() {
+        writeln!(writer, "{} -- {}", names.join(" | "), builtin.help())?;
+    }
+    if !class.classes.is_empty() {
+        writeln!(writer, "Classes:")?;
+        for class in class.classes.iter() {
+            writeln!(
+                writer,
+                "\t{} -- {}",
+                class.names().collect::<Vec<_>>().join(" | "),
+                class.help
+            )?;
+        }
+    }
+
+    if !class.actions.is_empty() {
+        writeln!(writer, "Actions:")?;
+        for action in class.actions.iter() {
+            writeln!(
+                writer,
+                "\t{}{} -- {}",
+                action.names().collect::<Vec<_>>().join(" | "),
+                arg_usage(action),
+                action.help
+            )?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_line_test() {
+        let mut cmder = Builder::default_config("test")
+            .begin_class("class1", "class1 help")
+            .begin_class("class1-class1", "adsf")
+            .add_action("action1", "adf", |_, _| ())
+            .end_class()
+            .begin_class("class1-class2", "adsf")
+            .add_action("action2", "adsf", |_, _| ())
+            .end_class()
+            .end_class()
+            .begin_class("class2", "asdf")
+            .end_class()
+            .add_action("test-args", "", |_wtr, args| {
+                assert_eq!(&args, &["one", "two", "three"])
+            })
+            .into_commander()
+            .unwrap();
+
+        let w = &mut std::io::sink();
+
+        let unrecognized = || LineResult::Unrecognized {
+            word: "adsf".to_string(),
+            suggestions: Vec::new(),
+        };
+        assert_eq!(cmder.parse_line("adsf", true, w), unrecognized()); // unrecognised branch
+        assert_eq!(cmder.current, cmder.root);
+        assert_eq!(cmder.parse_line("adsf", false, w), unrecognized()); // unrecognised branch
+        assert_eq!(cmder.current, cmder.root);
+
+        assert_eq!(cmder.parse_line("class1", true, w), LineResult::Class);
+        assert_ne!(cmder.current, cmder.root);
+        assert_eq!(cmder.current.name, "class1");
+
+        // should be able to action here
+        assert_eq!(
+            cmder.parse_line("class1-class1 action1", true, w),
+            LineResult::Action(())
+        );
+        assert_eq!(cmder.current.name, "class1");
+        assert_eq!(
+            cmder.parse_line("class1-class2 action2", true, w),
+            LineResult::Action(())
+        );
+        assert_eq!(cmder.current.name, "class1");
+
+        // get back to root
+        assert_eq!(cmder.parse_line("cancel", true, w), LineResult::Cancel);
+        assert_eq!(cmder.current.name, "test");
+
+        // test args
+        assert_eq!(
+            cmder.parse_line("test-args one two three", true, w),
+            LineResult::Action(())
+        );
+        assert_eq!(cmder.current.name, "test");
+
+        // test help
+        assert_eq!(cmder.parse_line("help", true, w), LineResult::Help);
+        assert_eq!(cmder.current.name, "test");
+        assert_eq!(cmder.parse_line("help", false, w), LineResult::Help);
+        assert_eq!(cmder.current.name, "test");
+
+        // test exit
+        assert_eq!(cmder.parse_line("exit", true, w), LineResult::Exit);
+    }
+
+    #[test]
+    fn parse_line_quoted_args_test() {
+        let mut cmder = Builder::new("test")
+            .add_action("echo", "", |_wtr, args| {
+                args.iter().map(|x| x.to_string()).collect::<Vec<_>>()
+            })
+            .into_commander()
+            .unwrap();
+
+        let w = &mut std::io::sink();
+
+        assert_eq!(
+            cmder.parse_line(r#"echo  "hello world"   'it''s' a\ b"#, true, w),
+            LineResult::Action(vec![
+                "hello world".to_string(),
+                "its".to_string(),
+                "a b".to_string()
+            ])
+        );
+        assert_eq!(
+            cmder.parse_line("echo \"hello world", true, w),
+            LineResult::UnterminatedQuote('"')
+        );
+        assert_eq!(cmder.parse_line("   ", true, w), LineResult::Class);
+    }
+
+    #[test]
+    fn parse_line_invalid_args_test() {
+        use args::{ArgSpec, ArgType};
+        let mut cmder = Builder::new("test")
+            .add_action_with_args(
+                "add",
+                "",
+                ArgSpec::new()
+                    .required("a", ArgType::Int)
+                    .with_default("b", ArgType::Int, "1"),
+                |_, args| args.get_int("a").unwrap() + args.get_int("b").unwrap(),
+            )
+            .into_commander()
+            .unwrap();
+
+        let mut w = Vec::new();
+
+        assert_eq!(
+            cmder.parse_line("add 2 3", false, &mut w),
+            LineResult::Action(5)
+        );
+        assert_eq!(
+            cmder.parse_line("add 2", false, &mut w),
+            LineResult::Action(3)
+        );
+        assert_eq!(
+            cmder.parse_line("add", false, &mut w),
+            LineResult::InvalidArgs(ArgError::Missing("a".to_string()))
+        );
+        assert!(String::from_utf8_lossy(&w).contains("missing argument 'a'\nusage: add <a> [b=1]"));
+
+        let mut help = Vec::new();
+        cmder.parse_line("help", false, &mut help);
+        assert!(String::from_utf8_lossy(&help).contains("\tadd <a> [b=1] -- \n"));
+    }
+
+    #[test]
+    fn parse_line_action_error_test() {
+        #[derive(Debug)]
+        struct Outer(io::Error);
+        impl fmt::Display for Outer {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                write!(f, "connecting failed")
+            }
+        }
+        impl Error for Outer {
+            fn source(&self) -> Option<&(dyn Error + 'static)> {
+                Some(&self.0)
+            }
+        }
+
+        let mut cmder = Builder::new("test")
+            .add_fallible_action("connect", "", |_, args| match args.first() {
+                Some(_) => Ok(()),
+                None => Err(Outer(io::Error::other("no host"))),
+            })
+            .into_commander()
+            .unwrap();
+
+        let mut w = Vec::new();
         assert_eq!(
-            cmder.parse_line("test-args one two three", true, w),
+            cmder.parse_line("connect host", false, &mut w),
             LineResult::Action(())
         );
-        assert_eq!(cmder.current.name, "test");
+        let r = cmder.parse_line("connect", false, &mut w);
+        assert!(!r.is_success());
+        assert_eq!(
+            r,
+            LineResult::ActionError(ActionError::new(Outer(io::Error::other("no host"))))
+        );
+        let out = String::from_utf8_lossy(&w);
+        assert!(out.contains("error: connecting failed"));
+        assert!(!out.contains("caused by"));
 
-        // test help
-        assert_eq!(cmder.parse_line("help", true, w), LineResult::Help);
-        assert_eq!(cmder.current.name, "test");
-        assert_eq!(cmder.parse_line("help", false, w), LineResult::Help);
-        assert_eq!(cmder.current.name, "test");
+        cmder.set_error_chain(true);
+        let mut w = Vec::new();
+        cmder.parse_line("connect", false, &mut w);
+        let out = String::from_utf8_lossy(&w);
+        assert!(out.contains("error: connecting failed"));
+        assert!(out.contains("  caused by: no host"));
+    }
 
-        // test exit
-        assert_eq!(cmder.parse_line("exit", true, w), LineResult::Exit);
+    /// A writer which fails after `remaining` bytes.
+    struct FailingWriter {
+        remaining: usize,
+    }
+
+    impl Write for FailingWriter {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            if self.remaining == 0 {
+                Err(io::Error::new(io::ErrorKind::BrokenPipe, "closed"))
+            } else {
+                let n = buf.len().min(self.remaining);
+                self.remaining -= n;
+                Ok(n)
+            }
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn try_parse_line_test() {
+        let mut cmder = Builder::new("test")
+            .begin_class("one", "")
+            .add_action("print", "", |wtr, _| writeln!(wtr, "printed").is_ok())
+            .add_fallible_action("fail", "", |_, _| Err::<bool, _>("failed"))
+            .into_commander()
+            .unwrap();
+
+        let w = &mut FailingWriter { remaining: 0 };
+
+        assert_eq!(
+            cmder.try_parse_line("one print", false, w).unwrap(),
+            LineResult::Action(false)
+        );
+        assert_eq!(
+            cmder.try_parse_line("one", false, w).unwrap(),
+            LineResult::Class
+        );
+        assert_eq!(cmder.path(), "test.one");
+
+        for line in &["help", "fail", "asdf", "'open"] {
+            let err = cmder.try_parse_line(line, false, w).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::BrokenPipe);
+            assert_eq!(cmder.path(), "test.one");
+        }
+
+        let err = cmder
+            .try_parse_chain("c; one fail; print", false, w)
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::BrokenPipe);
+        assert_eq!(cmder.path(), "test"); // cancelled, and restored after the failed action
+
+        let w = &mut FailingWriter { remaining: 100 };
+        assert_eq!(
+            cmder.try_parse_line("help", false, w).unwrap_err().kind(),
+            io::ErrorKind::BrokenPipe
+        );
+    }
+
+    #[test]
+    fn tokenize_test() {
+        assert_eq!(tokenize(""), Ok(vec![]));
+        assert_eq!(
+            tokenize(" one  two\tthree \n"),
+            Ok(vec_str(&["one", "two", "three"]))
+        );
+        assert_eq!(tokenize(r#"a "b c" d"#), Ok(vec_str(&["a", "b c", "d"])));
+        assert_eq!(tokenize(r#"'a "b' c"#), Ok(vec_str(&["a \"b", "c"])));
+        assert_eq!(tokenize(r#"a"b c"d"#), Ok(vec_str(&["ab cd"])));
+        assert_eq!(tokenize(r#""" ''"#), Ok(vec_str(&["", ""])));
+        assert_eq!(tokenize(r#"a\ b \"c\""#), Ok(vec_str(&["a b", "\"c\""])));
+        assert_eq!(
+            tokenize(r#""say \"hi\" \n""#),
+            Ok(vec_str(&[r#"say "hi" \n"#]))
+        );
+        assert_eq!(tokenize(r#"'\'"#), Ok(vec_str(&["\\"])));
+        assert_eq!(tokenize("trailing\\"), Ok(vec_str(&["trailing\\"])));
+        assert_eq!(tokenize(r#"open "quote"#), Err('"'));
+        assert_eq!(tokenize("open 'quote"), Err('\''));
+    }
+
+    #[test]
+    fn tokenize_chain_test() {
+        use Chain::*;
+        assert_eq!(
+            tokenize_chain("net reset; net status"),
+            Ok(vec![
+                (Always, vec_str(&["net", "reset"]), "net reset"),
+                (Always, vec_str(&["net", "status"]), "net status")
+            ])
+        );
+        assert_eq!(
+            tokenize_chain("a&&b||c"),
+            Ok(vec![
+                (Always, vec_str(&["a"]), "a"),
+                (And, vec_str(&["b"]), "b"),
+                (Or, vec_str(&["c"]), "c")
+            ])
+        );
+        assert_eq!(
+            tokenize_chain(r#"echo "a; b" c\;d e&f g|h"#),
+            Ok(vec![(
+                Always,
+                vec_str(&["echo", "a; b", "c;d", "e&f", "g|h"]),
+                r#"echo "a; b" c\;d e&f g|h"#
+            )])
+        );
+        assert_eq!(
+            tokenize_chain(" a ;"),
+            Ok(vec![(Always, vec_str(&["a"]), "a"), (Always, vec![], "")])
+        );
+        assert_eq!(tokenize_chain("a; 'b"), Err('\''));
+        // operators are only interpreted when chaining
+        assert_eq!(tokenize("a;b && c"), Ok(vec_str(&["a;b", "&&", "c"])));
+    }
+
+    #[test]
+    fn parse_chain_test() {
+        let mut cmder = Builder::new("test")
+            .begin_class("db", "")
+            .add_action("ok", "", |_, _| true)
+            .add_action("fail", "", |_, _| false)
+            .into_commander()
+            .unwrap();
+
+        let w = &mut std::io::sink();
+
+        assert_eq!(
+            cmder.parse_chain("db ok; db fail", true, w),
+            vec![LineResult::Action(true), LineResult::Action(false)]
+        );
+        assert_eq!(
+            cmder.parse_chain("db fail && db ok || db ok", true, w),
+            vec![LineResult::Action(false), LineResult::Action(true)]
+        );
+        assert_eq!(
+            cmder.parse_chain("db ok || db fail && db ok", true, w),
+            vec![LineResult::Action(true), LineResult::Action(true)]
+        );
+        assert_eq!(
+            cmder.parse_chain("asdf && db ok", true, w),
+            vec![LineResult::Unrecognized {
+                word: "asdf".to_string(),
+                suggestions: Vec::new()
+            }]
+        );
+        assert_eq!(
+            cmder.parse_chain("db; ok", true, w),
+            vec![LineResult::Class, LineResult::Action(true)]
+        );
+        assert_eq!(cmder.path(), "test.db");
+        assert_eq!(
+            cmder.parse_chain("exit; ok", true, w),
+            vec![LineResult::Exit]
+        );
+        assert_eq!(
+            cmder.parse_chain("ok; 'fail", true, w),
+            vec![LineResult::UnterminatedQuote('\'')]
+        );
+    }
+
+    #[test]
+    fn run_args_with_test() {
+        let mut cmder = Builder::new("mytool")
+            .begin_class("net", "network")
+            .add_action_with_args(
+                "status",
+                "net status",
+                args::ArgSpec::new().flag("json", None),
+                |wtr, args| write!(wtr, "{}", args.flag("json")).is_ok(),
+            )
+            .add_fallible_action("reset", "", |_, _| Err::<bool, _>("no link"))
+            .add_action_with_args(
+                "echo",
+                "",
+                args::ArgSpec::new().variadic("words", args::ArgType::Str),
+                |wtr, args| write!(wtr, "{}", args.get_all("words")[0].as_str().unwrap()).is_ok(),
+            )
+            .add_action_with_args(
+                "manual",
+                "",
+                args::ArgSpec::new().flag("help", None),
+                |wtr, args| write!(wtr, "manual").is_ok() && args.flag("help"),
+            )
+            .into_commander()
+            .unwrap();
+
+        let mut run = |args: &[&str]| {
+            let mut out = Vec::new();
+            let code = cmder
+                .run_args_with(args.iter().copied(), false, &mut out)
+                .unwrap();
+            (code, String::from_utf8(out).unwrap())
+        };
+
+        assert_eq!(
+            run(&["mytool", "net", "status", "--json"]),
+            (0, "true".to_string())
+        );
+        assert_eq!(
+            run(&["mytool", "net", "reset"]),
+            (1, "error: no link\n".to_string())
+        );
+        assert_eq!(run(&["mytool", "net", "status", "x"]).0, 2);
+        assert_eq!(run(&["mytool", "asdf"]).0, 2);
+        assert_eq!(
+            run(&["mytool", "net", "status", "--help"]),
+            (0, "status [--json] -- net status\n".to_string())
+        );
+
+        let (code, help) = run(&["mytool", "net"]);
+        assert_eq!(code, 2);
+        assert!(help.contains("\tstatus [--json] -- net status"));
+        let (code, help) = run(&["mytool", "--help", "net"]);
+        assert_eq!(code, 0);
+        assert!(help.contains("\tstatus [--json] -- net status"));
+        let (code, help) = run(&["mytool"]);
+        assert_eq!(code, 2);
+        assert!(help.contains("\tnet -- network"));
+        let (code, help) = run(&["mytool", "asdf", "--help"]);
+        assert_eq!(code, 0);
+        assert!(help.contains("\tnet -- network"));
+
+        // after the action, a --help following -- or declared by the action is an argument
+        assert_eq!(
+            run(&["mytool", "net", "echo", "--", "--help"]),
+            (0, "--help".to_string())
+        );
+        assert_eq!(
+            run(&["mytool", "net", "manual", "--help"]),
+            (0, "manual".to_string())
+        );
+        assert_eq!(
+            run(&["mytool", "net", "--help", "manual", "--help"]),
+            (0, "manual [--help] -- \n".to_string())
+        );
+
+        assert_eq!(cmder.path(), "mytool");
+    }
+
+    #[test]
+    fn unrecognized_suggestions_test() {
+        let mut cmder = Builder::new("test")
+            .begin_class("network", "")
+            .end_class()
+            .add_action("status", "", |_, _| ())
+            .add_action("stats", "", |_, _| ())
+            .into_commander()
+            .unwrap();
+
+        let mut out = Vec::new();
+        assert_eq!(
+            cmder.parse_line("stat", false, &mut out),
+            LineResult::Unrecognized {
+                word: "stat".to_string(),
+                suggestions: vec_str(&["stats", "status"]),
+            }
+        );
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "'stat' does not match any keywords, classes, or actions\ndid you mean: stats, status?\n"
+        );
+
+        let mut suggest = |line| match cmder.parse_line(line, false, &mut std::io::sink()) {
+            LineResult::Unrecognized { suggestions, .. } => suggestions,
+            r => panic!("expected unrecognized, got {:?}", r),
+        };
+        assert_eq!(suggest("NETWRK"), vec_str(&["network"]));
+        assert_eq!(suggest("hepl"), vec_str(&["help"]));
+        assert_eq!(suggest("ex"), vec_str(&["exit"]));
+        assert_eq!(suggest("zzz"), Vec::<String>::new());
+    }
+
+    #[test]
+    fn edit_distance_test() {
+        assert_eq!(edit_distance("", ""), 0);
+        assert_eq!(edit_distance("abc", ""), 3);
+        assert_eq!(edit_distance("", "abc"), 3);
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+        assert_eq!(edit_distance("status", "stats"), 1);
+        assert_eq!(edit_distance("hepl", "help"), 1);
+    }
+
+    fn vec_str(v: &[&str]) -> Vec<String> {
+        v.iter().map(|x| x.to_string()).collect()
     }
 
     #[test]
     fn parse_word_test() {
         let mut sc = SubClass::with_name("Class-Name", "help msg");
-        assert_eq!(parse_word(&sc, "HELP"), WordResult::Help(&sc));
-        assert_eq!(parse_word(&sc, "EXIT"), WordResult::Exit);
-        assert_eq!(parse_word(&sc, "CANCEL"), WordResult::Cancel);
-        assert_eq!(parse_word(&sc, "C"), WordResult::Cancel);
-        assert_eq!(parse_word(&sc, "asdf"), WordResult::Unrecognized);
+        assert_eq!(
+            parse_word(&sc, "HELP", &Builtins::default(), false),
+            WordResult::Help(&sc)
+        );
+        assert_eq!(
+            parse_word(&sc, "EXIT", &Builtins::default(), false),
+            WordResult::Exit
+        );
+        assert_eq!(
+            parse_word(&sc, "CANCEL", &Builtins::default(), false),
+            WordResult::Cancel
+        );
+        assert_eq!(
+            parse_word(&sc, "C", &Builtins::default(), false),
+            WordResult::Cancel
+        );
+        assert_eq!(
+            parse_word(&sc, "asdf", &Builtins::default(), false),
+            WordResult::Unrecognized
+        );
 
         sc.classes
             .push(Arc::new(SubClass::with_name("name", "asdf")));
         sc.actions.push(Action::blank_fn("action", "adsf"));
-        assert_eq!(parse_word(&sc, "NAME"), WordResult::Class(&sc.classes[0]));
         assert_eq!(
-            parse_word(&sc, "aCtIoN"),
+            parse_word(&sc, "NAME", &Builtins::default(), false),
+            WordResult::Class(&sc.classes[0])
+        );
+        assert_eq!(
+            parse_word(&sc, "aCtIoN", &Builtins::default(), false),
             WordResult::Action(&sc.actions[0])
         );
     }
 
+    #[test]
+    fn parse_abbreviation_test() {
+        let mut sc = SubClass::with_name("Class-Name", "help msg");
+        sc.classes
+            .push(Arc::new(SubClass::with_name("show", "asdf")));
+        sc.actions.push(Action::blank_fn("shutdown", "adsf"));
+        sc.actions.push(Action::blank_fn("interfaces", "adsf"));
+
+        assert_eq!(
+            parse_word(&sc, "int", &Builtins::default(), false),
+            WordResult::Unrecognized
+        );
+        assert_eq!(
+            parse_word(&sc, "INT", &Builtins::default(), true),
+            WordResult::Action(&sc.actions[1])
+        );
+        assert_eq!(
+            parse_word(&sc, "sho", &Builtins::default(), true),
+            WordResult::Class(&sc.classes[0])
+        );
+        assert_eq!(
+            parse_word(&sc, "sh", &Builtins::default(), true),
+            WordResult::Ambiguous(vec_str(&["show", "shutdown"]))
+        );
+        assert_eq!(
+            parse_word(&sc, "x", &Builtins::default(), true),
+            WordResult::Unrecognized
+        );
+        assert_eq!(
+            parse_word(&sc, "c", &Builtins::default(), true),
+            WordResult::Cancel
+        );
+    }
+
+    #[test]
+    fn alias_test() {
+        let mut cmder = Builder::new("test")
+            .begin_class("list", "list things")
+            .add_action("all", "everything", |_, _| ())
+            .add_alias("all", "a")
+            .add_alias("all", "*")
+            .end_class()
+            .add_alias("list", "ls")
+            .into_commander()
+            .unwrap();
+
+        let w = &mut std::io::sink();
+        assert_eq!(cmder.parse_line("LS *", false, w), LineResult::Action(()));
+        assert_eq!(cmder.parse_line("list a", false, w), LineResult::Action(()));
+
+        let mut help = Vec::new();
+        cmder.parse_line("help", false, &mut help);
+        assert!(String::from_utf8(help)
+            .unwrap()
+            .contains("\tlist | ls -- list things\n"));
+
+        let mut help = Vec::new();
+        cmder.parse_line("ls help", false, &mut help);
+        assert!(String::from_utf8(help)
+            .unwrap()
+            .contains("\tall | a | * -- everything\n"));
+
+        cmder.set_abbreviations(true);
+        assert_eq!(cmder.parse_line("l", false, w), LineResult::Class);
+    }
+
+    #[test]
+    fn alias_builtin_test() {
+        let mut cmder = Builder::new("test")
+            .begin_class("env", "")
+            .add_action("release", "", |_, args| args.join(","))
+            .into_commander()
+            .unwrap();
+
+        let w = &mut std::io::sink();
+        assert_eq!(
+            cmder.parse_line("alias deploy = env release", false, w),
+            LineResult::Unrecognized {
+                word: "alias".to_string(),
+                suggestions: Vec::new()
+            }
+        );
+
+        cmder.set_alias_builtin(true).unwrap();
+        assert_eq!(
+            cmder.parse_line("alias deploy = env release 'a b' prod", false, w),
+            LineResult::Alias
+        );
+        assert_eq!(
+            cmder.parse_line("alias r=env release", false, w),
+            LineResult::Alias
+        );
+        assert_eq!(
+            cmder.parse_line("DEPLOY push", false, w),
+            LineResult::Action("a b,prod,push".to_string())
+        );
+        assert_eq!(
+            cmder.parse_line("r", false, w),
+            LineResult::Action(String::new())
+        );
+
+        let mut out = Vec::new();
+        cmder.parse_line("alias", false, &mut out);
+        cmder.parse_line("alias r", false, &mut out);
+        cmder.parse_line("alias nope", false, &mut out);
+        cmder.parse_line("alias x =", false, &mut out);
+        cmder.parse_line("alias exit = env", false, &mut out);
+        cmder.parse_line("unalias r", false, &mut out);
+        cmder.parse_line("unalias r", false, &mut out);
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "deploy = env release 'a b' prod\n\
+             r = env release\n\
+             r = env release\n\
+             no alias 'nope'\n\
+             usage: alias <name> = <command...>\n\
+             'exit' is the name of a builtin\n\
+             no alias 'r'\n"
+        );
+        assert_eq!(cmder.runtime_aliases().len(), 1);
+
+        let mut help = Vec::new();
+        cmder.parse_line("help", false, &mut help);
+        assert!(String::from_utf8(help)
+            .unwrap()
+            .contains("unalias -- removes an alias\n"));
+    }
+
+    #[test]
+    fn alias_expands_to_builtin_test() {
+        let mut cmder = Builder::new("test")
+            .begin_class("one", "")
+            .begin_class("two", "")
+            .add_action("echo", "", |_, args| args.join(","))
+            .into_commander()
+            .unwrap();
+        cmder.set_alias_builtin(true).unwrap();
+        cmder.set_history_builtins(true).unwrap();
+
+        let w = &mut std::io::sink();
+        cmder.set_runtime_alias("b", "back").unwrap();
+        cmder.set_runtime_alias("deep", "pushd one two").unwrap();
+        cmder.set_runtime_alias("loop", "pushd loop").unwrap();
+        cmder.set_runtime_alias("al", "alias").unwrap();
+
+        assert_eq!(cmder.parse_line("deep", false, w), LineResult::Class);
+        assert_eq!(cmder.path(), "test.one.two");
+        assert_eq!(cmder.parse_line("b", false, w), LineResult::Class);
+        assert_eq!(cmder.path(), "test");
+        assert_eq!(
+            cmder.parse_line("deep echo a", false, w),
+            LineResult::Action("a".to_string())
+        );
+        assert_eq!(cmder.path(), "test");
+
+        // the expansion is not expanded again
+        assert_eq!(
+            cmder.parse_line("loop", false, w),
+            LineResult::Unrecognized {
+                word: "loop".to_string(),
+                suggestions: Vec::new()
+            }
+        );
+
+        assert_eq!(
+            cmder.parse_line("al e = one two echo", false, w),
+            LineResult::Alias
+        );
+        assert_eq!(
+            cmder.parse_line("e x", false, w),
+            LineResult::Action("x".to_string())
+        );
+        let mut out = Vec::new();
+        cmder.parse_line("al e", false, &mut out);
+        assert_eq!(String::from_utf8(out).unwrap(), "e = one two echo\n");
+    }
+
+    #[test]
+    fn alias_definition_test() {
+        let def = |words: &[&str]| alias_definition(&vec_str(words));
+        let expected = Some(("a".to_string(), "b c".to_string()));
+        assert_eq!(def(&["a", "=", "b", "c"]), expected);
+        assert_eq!(def(&["a=", "b", "c"]), expected);
+        assert_eq!(def(&["a", "=b", "c"]), expected);
+        assert_eq!(def(&["a=b", "c"]), expected);
+        assert_eq!(
+            def(&["a", "=", "it's", "x;y", ""]),
+            Some(("a".to_string(), "'it'\\''s' 'x;y' ''".to_string()))
+        );
+        assert_eq!(def(&["a"]), None);
+        assert_eq!(def(&["a", "b"]), None);
+        assert_eq!(def(&["a", "="]), None);
+        assert_eq!(def(&["=b"]), None);
+    }
+
+    #[test]
+    fn quote_word_round_trip_test() {
+        for word in &["plain", "two words", "it's", "a\\b", "\"q\"", "x&&y", ""] {
+            assert_eq!(tokenize(&quote_word(word)), Ok(vec_str(&[word])));
+        }
+    }
+
+    #[test]
+    fn path_navigation_test() {
+        let mut cmder = Builder::new("base")
+            .begin_class("one", "")
+            .begin_class("two", "")
+            .add_action("echo", "", |_, args| args.join(" "))
+            .end_class()
+            .end_class()
+            .begin_class("three", "")
+            .into_commander()
+            .unwrap();
+        let w = &mut std::io::sink();
+
+        assert_eq!(cmder.parse_line("one.two", false, w), LineResult::Class);
+        assert_eq!(cmder.path(), "base.one.two");
+        assert_eq!(cmder.parse_line("..", false, w), LineResult::Class);
+        assert_eq!(cmder.path(), "base.one");
+        assert_eq!(cmder.parse_line(".. ..", false, w), LineResult::Class);
+        assert!(cmder.at_root());
+        assert_eq!(cmder.path(), "base");
+
+        assert_eq!(
+            cmder.parse_line("one .. three", false, w),
+            LineResult::Class
+        );
+        assert_eq!(cmder.path(), "base.three");
+        assert_eq!(cmder.parse_line("/one.two", false, w), LineResult::Class);
+        assert_eq!(cmder.path(), "base.one.two");
+        assert_eq!(cmder.parse_line("base.three", false, w), LineResult::Class);
+        assert_eq!(cmder.path(), "base.three");
+        assert_eq!(cmder.parse_line("/ one", false, w), LineResult::Class);
+        assert_eq!(cmder.path(), "base.one");
+
+        // actions end a path and keep the position
+        assert_eq!(
+            cmder.parse_line("/one.two.echo a b", false, w),
+            LineResult::Action("a b".to_string())
+        );
+        assert_eq!(
+            cmder.parse_line("../one.two.echo", false, w),
+            LineResult::Unrecognized {
+                word: "../one.two.echo".to_string(),
+                suggestions: Vec::new()
+            }
+        );
+        assert_eq!(
+            cmder.parse_line("two.echo.x", false, w),
+            LineResult::Unrecognized {
+                word: "two.echo.x".to_string(),
+                suggestions: Vec::new()
+            }
+        );
+        assert_eq!(
+            cmder.parse_line("/one.tow", false, w),
+            LineResult::Unrecognized {
+                word: "tow".to_string(),
+                suggestions: vec!["two".to_string()]
+            }
+        );
+        assert_eq!(cmder.path(), "base.one");
+    }
+
+    #[test]
+    fn configured_builtins_test() {
+        let mut cmder = Builder::new("test")
+            .with_builtins(
+                Builtins::default()
+                    .disable(Builtin::Cancel)
+                    .rename(Builtin::Exit, "quit")
+                    .alias(Builtin::Exit, "q")
+                    .rename(Builtin::Alias, "def"),
+            )
+            .begin_class("one", "")
+            .add_action("exit", "", |_, _| ())
+            .into_commander()
+            .unwrap();
+
+        let w = &mut std::io::sink();
+        assert_eq!(
+            cmder.parse_line("one exit", false, w),
+            LineResult::Action(())
+        );
+        assert_eq!(cmder.parse_line("Q", false, w), LineResult::Exit);
+        assert_eq!(
+            cmder.parse_line("def x = one exit", false, w),
+            LineResult::Alias
+        );
+        assert_eq!(cmder.parse_line("x", false, w), LineResult::Action(()));
+
+        let mut out = Vec::new();
+        cmder.parse_line("cancel", false, &mut out);
+        cmder.parse_line("help", false, &mut out);
+        cmder.parse_line("def x y", false, &mut out);
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "'cancel' does not match any keywords, classes, or actions\n\
+             help -- prints the help messages\n\
+             .. -- moves up to the parent class\n\
+             / -- moves to the root class, or prefixes a path from the root\n\
+             quit | q -- sends the exit signal to end the interactive loop\n\
+             def -- lists, shows or defines aliases, define with <name> = <command...>\n\
+             Classes:\n\
+             \tone -- \n\
+             usage: def <name> = <command...>\n"
+        );
+    }
+
     #[test]
     fn write_help_coloured_test() {
         let mut sc = SubClass::with_name("Class-Name", "root class");
@@ -318,7 +2298,7 @@ mod tests {
             .push(Action::blank_fn("action2", "action 2 help"));
 
         let mut help = Vec::new();
-        write_help_coloured(&sc, &mut help).unwrap();
+        write_help_coloured(&sc, &Builtins::default(), &mut help).unwrap();
         let help = String::from_utf8_lossy(&help);
 
         assert_eq!(
@@ -326,6 +2306,8 @@ mod tests {
             &format!(
                 r#"{} -- prints the help messages
 {} | {} -- returns to the root class
+{} -- moves up to the parent class
+{} -- moves to the root class, or prefixes a path from the root
 {} -- sends the exit signal to end the interactive loop
 {}
 	{} -- class 1 help
@@ -337,6 +2319,8 @@ mod tests {
                 "help".bright_yellow(),
                 "cancel".bright_yellow(),
                 "c".bright_yellow(),
+                "..".bright_yellow(),
+                "/".bright_yellow(),
                 "exit".bright_yellow(),
                 "Classes:".bright_purple(),
                 "class1".bright_yellow(),
@@ -361,13 +2345,15 @@ mod tests {
             .push(Action::blank_fn("action2", "action 2 help"));
 
         let mut help = Vec::new();
-        write_help(&sc, &mut help).unwrap();
+        write_help(&sc, &Builtins::default(), &mut help).unwrap();
         let help = String::from_utf8_lossy(&help);
 
         assert_eq!(
             &help,
             r#"help -- prints the help messages
 cancel | c -- returns to the root class
+.. -- moves up to the parent class
+/ -- moves to the root class, or prefixes a path from the root
 exit -- sends the exit signal to end the interactive loop
 Classes:
 	class1 -- class 1 help
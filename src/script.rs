@@ -0,0 +1,292 @@
+//! Running files of commands through a `Commander`.
+//!
+//! A script is a sequence of lines, each parsed with [`try_parse_chain`](Commander::try_parse_chain),
+//! so commands can be chained with `;`, `&&` and `||` as in the run loop.
+//!
+//! - Blank lines are skipped.
+//! - Lines starting with `#` are comments and skipped.
+//! - A line ending in an unescaped backslash continues on the next line.
+//!
+//! # Example
+//!
+//! ```rust
+//! use cmdtree::*;
+//! use cmdtree::script::ErrorPolicy;
+//!
+//! let mut cmder = Builder::new("base")
+//!     .begin_class("db", "")
+//!     .add_action("connect", "", |_wtr, args| args.len() == 1)
+//!     .into_commander().unwrap();
+//!
+//! let script = "# setup the database\n\
+//!               db\n\
+//!               \n\
+//!               connect \\\n    localhost\n\
+//!               connect\n\
+//!               connect never-run\n";
+//!
+//! let report = cmder
+//!     .run_script_from(script.as_bytes(), ErrorPolicy::Stop, &mut std::io::sink())
+//!     .unwrap();
+//!
+//! assert_eq!(report.lines.len(), 3);
+//! assert_eq!(report.lines[1], (4, LineResult::Action(true)));
+//! assert_eq!(report.first_failure(), Some(&(6, LineResult::Action(false))));
+//! ```
+use super::*;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+/// What to do when a line of a script fails.
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ErrorPolicy {
+    /// Stop running the script at the first failed line.
+    Stop,
+    /// Continue running the remaining lines.
+    Continue,
+}
+
+/// The outcome of running a script.
+#[derive(Debug, PartialEq)]
+pub struct ScriptReport<R> {
+    /// The result of each command that was run, paired with the (1-based) line number it starts on.
+    /// A line of chained commands has a result for each command that was run.
+    pub lines: Vec<(usize, LineResult<R>)>,
+}
+
+impl<R: ActionOutcome> ScriptReport<R> {
+    /// The lines which failed, as determined by [`LineResult::is_success`].
+    pub fn failures(&self) -> impl Iterator<Item = &(usize, LineResult<R>)> {
+        self.lines.iter().filter(|(_, r)| !r.is_success())
+    }
+
+    /// The first line which failed, if any.
+    pub fn first_failure(&self) -> Option<&(usize, LineResult<R>)> {
+        self.failures().next()
+    }
+
+    /// Returns `true` if no lines failed.
+    pub fn is_success(&self) -> bool {
+        self.first_failure().is_none()
+    }
+}
+
+impl<R, S> Commander<R, S> {
+    /// Run a file of commands. See the [`script` module](crate::script) for the file format.
+    ///
+    /// Each line is parsed with [`try_parse_chain`](Commander::try_parse_chain) without colour,
+    /// and the `Commander` keeps its position between lines, just as in the run loop.
+    /// The script ends early if an `exit` command is reached,
+    /// or if the last command run on a line fails and the policy is `ErrorPolicy::Stop`.
+    ///
+    /// Errors from opening or reading the file, or writing to `writer`, are returned.
+    pub fn run_script<P: AsRef<Path>, W: Write>(
+        &mut self,
+        path: P,
+        policy: ErrorPolicy,
+        writer: &mut W,
+    ) -> io::Result<ScriptReport<R>>
+    where
+        R: ActionOutcome,
+    {
+        let file = File::open(path)?;
+        self.run_script_from(BufReader::new(file), policy, writer)
+    }
+
+    /// Run commands read from `reader`. See [`run_script`](Commander::run_script).
+    pub fn run_script_from<B: BufRead, W: Write>(
+        &mut self,
+        reader: B,
+        policy: ErrorPolicy,
+        writer: &mut W,
+    ) -> io::Result<ScriptReport<R>>
+    where
+        R: ActionOutcome,
+    {
+        let mut lines = Vec::new();
+        let mut command = String::new();
+        let mut start = 0;
+
+        for (idx, line) in reader.lines().enumerate() {
+            let line = line?;
+
+            if command.is_empty() {
+                start = idx + 1;
+                let trimmed = line.trim_start();
+                if trimmed.is_empty() || trimmed.starts_with('#') {
+                    continue;
+                }
+            }
+
+            if let Some(continued) = continuation(&line) {
+                command.push_str(continued);
+                continue;
+            }
+
+            command.push_str(&line);
+            let results = self.try_parse_chain(&command, false, writer)?;
+            command.clear();
+
+            let stop = results.last().is_some_and(|r| {
+                matches!(r, LineResult::Exit) || (policy == ErrorPolicy::Stop && !r.is_success())
+            });
+            lines.extend(results.into_iter().map(|r| (start, r)));
+
+            if stop {
+                return Ok(ScriptReport { lines });
+            }
+        }
+
+        // a continuation on the last line
+        if !command.is_empty() {
+            let results = self.try_parse_chain(&command, false, writer)?;
+            lines.extend(results.into_iter().map(|r| (start, r)));
+        }
+
+        Ok(ScriptReport { lines })
+    }
+}
+
+/// If the line ends in an unescaped backslash, returns the line without it.
+fn continuation(line: &str) -> Option<&str> {
+    let trailing = line.chars().rev().take_while(|&c| c == '\\').count();
+    if trailing % 2 == 1 {
+        Some(&line[..line.len() - 1])
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cmder() -> Commander<bool> {
+        Builder::new("base")
+            .begin_class("one", "")
+            .add_action("args", "", |_, args| args.len() == 2)
+            .into_commander()
+            .unwrap()
+    }
+
+    #[test]
+    fn continuation_test() {
+        assert_eq!(continuation("a \\"), Some("a "));
+        assert_eq!(continuation("a \\\\"), None);
+        assert_eq!(continuation("a \\\\\\"), Some("a \\\\"));
+        assert_eq!(continuation("a"), None);
+    }
+
+    #[test]
+    fn run_script_from_test() {
+        let script = "  # comment\n\
+                      one args a b\n\
+                      one args \\\n\
+                      a \\\n\
+                      b\n\
+                      \t\n\
+                      one args a\n\
+                      asdf\n\
+                      one\n\
+                      args a b";
+
+        let report = cmder()
+            .run_script_from(script.as_bytes(), ErrorPolicy::Continue, &mut io::sink())
+            .unwrap();
+        assert_eq!(
+            report.lines,
+            vec![
+                (2, LineResult::Action(true)),
+                (3, LineResult::Action(true)),
+                (7, LineResult::Action(false)),
+                (
+                    8,
+                    LineResult::Unrecognized {
+                        word: "asdf".to_string(),
+                        suggestions: Vec::new()
+                    }
+                ),
+                (9, LineResult::Class),
+                (10, LineResult::Action(true)),
+            ]
+        );
+        assert_eq!(
+            report.failures().map(|x| x.0).collect::<Vec<_>>(),
+            vec![7, 8]
+        );
+        assert!(!report.is_success());
+
+        let report = cmder()
+            .run_script_from(script.as_bytes(), ErrorPolicy::Stop, &mut io::sink())
+            .unwrap();
+        assert_eq!(report.lines.len(), 3);
+        assert_eq!(
+            report.first_failure(),
+            Some(&(7, LineResult::Action(false)))
+        );
+    }
+
+    #[test]
+    fn run_script_exit_test() {
+        let report = cmder()
+            .run_script_from(
+                "one args a b\nexit\none args a".as_bytes(),
+                ErrorPolicy::Continue,
+                &mut io::sink(),
+            )
+            .unwrap();
+        assert_eq!(
+            report.lines,
+            vec![(1, LineResult::Action(true)), (2, LineResult::Exit)]
+        );
+        assert!(report.is_success());
+
+        // trailing continuation still runs
+        let report = cmder()
+            .run_script_from(
+                "one args a \\\nb \\".as_bytes(),
+                ErrorPolicy::Continue,
+                &mut io::sink(),
+            )
+            .unwrap();
+        assert_eq!(report.lines, vec![(1, LineResult::Action(true))]);
+    }
+
+    #[test]
+    fn run_script_chain_test() {
+        let script = "one\n\
+                      args a && args a b; args a b\n\
+                      args a || args a b\n\
+                      args a b && args a\n\
+                      args a b";
+
+        let report = cmder()
+            .run_script_from(script.as_bytes(), ErrorPolicy::Stop, &mut io::sink())
+            .unwrap();
+        assert_eq!(
+            report.lines,
+            vec![
+                (1, LineResult::Class),
+                (2, LineResult::Action(false)),
+                (2, LineResult::Action(true)),
+                (3, LineResult::Action(false)),
+                (3, LineResult::Action(true)),
+                (4, LineResult::Action(true)),
+                (4, LineResult::Action(false)),
+            ]
+        );
+    }
+
+    #[test]
+    fn run_script_missing_file_test() {
+        let err = cmder()
+            .run_script(
+                "this-file-does-not-exist.cmds",
+                ErrorPolicy::Stop,
+                &mut io::sink(),
+            )
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+    }
+}
@@ -0,0 +1,164 @@
+//! A hierarchical view of the command tree.
+//!
+//! [`Commander::structure`] flattens the tree into dotted paths, [`Commander::tree`] keeps its shape,
+//! with each class holding its classes and actions in the order they were added.
+//!
+//! With the `serde` feature the tree implements `Serialize`, and
+//! [`write_tree_json`](Commander::write_tree_json) writes it as JSON.
+//!
+//! # Example
+//!
+//! ```rust
+//! use cmdtree::*;
+//!
+//! let cmder = Builder::default_config("base")
+//!     .begin_class("one", "the first class")
+//!     .add_action("echo", "repeats stuff", |_, _| ())
+//!     .into_commander().unwrap();
+//!
+//! let tree = cmder.tree(true);
+//! assert_eq!(tree.name, "base");
+//! assert_eq!(tree.classes[0].name, "one");
+//! assert_eq!(tree.classes[0].actions[0].help, "repeats stuff");
+//! ```
+use super::*;
+use args::ArgSpec;
+
+/// A class of the command tree, with its nested classes and actions.
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ClassTree {
+    /// The class name.
+    pub name: String,
+    /// Other names the class is matched by.
+    pub aliases: Vec<String>,
+    /// The help message.
+    pub help: String,
+    /// The nested classes.
+    pub classes: Vec<ClassTree>,
+    /// The actions of the class.
+    pub actions: Vec<ActionTree>,
+}
+
+/// An action of the command tree.
+#[derive(Debug, PartialEq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct ActionTree {
+    /// The action name.
+    pub name: String,
+    /// Other names the action is matched by.
+    pub aliases: Vec<String>,
+    /// The help message.
+    pub help: String,
+    /// The usage of the arguments, such as `[--verbose] <n>`. Empty if the action has no argument specification.
+    pub usage: String,
+    /// The argument specification, if the action was added with one.
+    pub args: Option<ArgSpec>,
+}
+
+impl<R, S> Commander<R, S> {
+    /// The command tree from the root class or the current class.
+    pub fn tree(&self, from_root: bool) -> ClassTree {
+        class_tree(if from_root { &self.root } else { &self.current })
+    }
+
+    /// Write the command tree from the root class or the current class as pretty printed JSON.
+    ///
+    /// # Example
+    /// ```rust
+    /// # use cmdtree::*;
+    /// let cmder = Builder::<()>::new("base")
+    ///     .begin_class("one", "")
+    ///     .into_commander().unwrap();
+    ///
+    /// let mut json = Vec::new();
+    /// cmder.write_tree_json(true, &mut json).unwrap();
+    /// assert!(String::from_utf8(json).unwrap().contains(r#""name": "one""#));
+    /// ```
+    #[cfg(feature = "serde")]
+    pub fn write_tree_json<W: Write>(&self, from_root: bool, writer: &mut W) -> io::Result<()> {
+        serde_json::to_writer_pretty(&mut *writer, &self.tree(from_root))?;
+        writeln!(writer)
+    }
+}
+
+fn class_tree<R, S>(class: &SubClass<R, S>) -> ClassTree {
+    ClassTree {
+        name: class.name.clone(),
+        aliases: class.aliases.clone(),
+        help: class.help.to_string(),
+        classes: class.classes.iter().map(|c| class_tree(c)).collect(),
+        actions: class
+            .actions
+            .iter()
+            .map(|a| ActionTree {
+                name: a.name.clone(),
+                aliases: a.aliases.clone(),
+                help: a.help.to_string(),
+                usage: a.args.as_ref().map(ArgSpec::usage).unwrap_or_default(),
+                args: a.args.clone(),
+            })
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use args::ArgType;
+
+    fn cmder() -> Commander<()> {
+        Builder::new("base")
+            .begin_class("one", "one help")
+            .begin_class("two", "")
+            .add_action_with_args(
+                "count",
+                "counts",
+                ArgSpec::new().required("n", ArgType::Int),
+                |_, _| (),
+            )
+            .end_class()
+            .end_class()
+            .add_alias("one", "1")
+            .add_action("act", "act help", |_, _| ())
+            .into_commander()
+            .unwrap()
+    }
+
+    #[test]
+    fn tree_test() {
+        let mut cmder = cmder();
+        let tree = cmder.tree(true);
+        assert_eq!(tree.name, "base");
+        assert_eq!(tree.actions.len(), 1);
+        assert_eq!(tree.actions[0].usage, "");
+        assert_eq!(tree.actions[0].args, None);
+
+        let one = &tree.classes[0];
+        assert_eq!(one.aliases, vec!["1".to_string()]);
+        assert_eq!(one.help, "one help");
+        let count = &one.classes[0].actions[0];
+        assert_eq!(count.name, "count");
+        assert_eq!(count.usage, "<n>");
+
+        cmder.parse_line("one two", false, &mut io::sink());
+        assert_eq!(cmder.tree(false), one.classes[0]);
+        assert_eq!(cmder.tree(true), tree);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn write_tree_json_test() {
+        let mut json = Vec::new();
+        cmder().write_tree_json(false, &mut json).unwrap();
+        let json: serde_json::Value = serde_json::from_slice(&json).unwrap();
+
+        assert_eq!(json["name"], "base");
+        assert_eq!(json["classes"][0]["aliases"][0], "1");
+        let count = &json["classes"][0]["classes"][0]["actions"][0];
+        assert_eq!(count["name"], "count");
+        assert_eq!(count["args"]["args"][0]["name"], "n");
+        assert_eq!(count["args"]["args"][0]["argtype"], "Int");
+        assert_eq!(json["actions"][0]["args"], serde_json::Value::Null);
+    }
+}